@@ -0,0 +1,488 @@
+use std::f32::consts::PI;
+
+use crate::bullet::*;
+use crate::commands::*;
+use crate::consts::*;
+use crate::enemy_sub::get_enemy_sub_vertices;
+use crate::explosion::Blast;
+use crate::fish::angle_diff;
+use crate::mine::*;
+use crate::render::*;
+use crate::seabed::*;
+use crate::ship::*;
+use crate::surface_verts::*;
+use crate::timestep::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BossKind {
+    Squid,
+    MotherSub,
+}
+
+impl BossKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            BossKind::Squid => "GIANT SQUID",
+            BossKind::MotherSub => "MOTHER SUB",
+        }
+    }
+
+    // Offsets from the boss position and how many blasts each one takes
+    fn weak_points(self) -> Vec<(Vector2, i32)> {
+        match self {
+            BossKind::Squid => vec![
+                (Vector2 { x: -12.0, y: -12.0 }, 5), // Upper eye
+                (Vector2 { x: -12.0, y: 12.0 }, 5),  // Lower eye
+                (Vector2 { x: -32.0, y: 0.0 }, 6),   // Beak
+            ],
+            BossKind::MotherSub => vec![
+                (Vector2 { x: 6.0, y: 12.0 }, 5),   // Torpedo tube
+                (Vector2 { x: 6.0, y: -12.0 }, 5),  // Sonar dome
+                (Vector2 { x: 44.0, y: -24.0 }, 6), // Mine hatch
+            ],
+        }
+    }
+
+    // Where it settles, measured from the right edge of the window
+    fn station_margin(self) -> f32 {
+        match self {
+            BossKind::Squid => 170.0,
+            BossKind::MotherSub => 220.0,
+        }
+    }
+
+    // Seconds between two salvos, per phase
+    fn fire_interval(self, phase: usize) -> f32 {
+        match self {
+            BossKind::Squid => [2.2, 1.8, 1.2][phase],
+            BossKind::MotherSub => [1.8, 1.6, 1.4][phase],
+        }
+    }
+}
+
+pub fn get_squid_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    [
+        (-36.0, -6.0),  // Top of the beak
+        (-36.0, 6.0),   // Bottom of the beak
+        (-20.0, -24.0), // Top of the head
+        (-20.0, 24.0),  // Bottom of the head
+        (20.0, -32.0),  // Upper mantle
+        (20.0, 32.0),   // Lower mantle
+        (70.0, -20.0),  // Upper fin
+        (70.0, 20.0),   // Lower fin
+        (110.0, 0.0),   // Tip of the mantle
+    ]
+    .iter()
+    .map(|(vx, vy)| Vector2 {
+        x: x + vx,
+        y: y + vy,
+    })
+    .collect()
+}
+
+/// The enemy submarine hull, four times the size.
+pub fn get_mother_sub_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    get_enemy_sub_vertices(0.0, 0.0)
+        .iter()
+        .map(|v| Vector2 {
+            x: x + v.x * 4.0,
+            y: y + v.y * 4.0,
+        })
+        .collect()
+}
+
+const WEAK_POINT_RADIUS: f32 = 8.0;
+const HIT_FLASH: f32 = 0.15;
+const ENTER_SPEED: f32 = 60.0;
+const TRACK_SPEED: f32 = 25.0;
+const SURFACE_CLEARANCE: f32 = 60.0;
+const FLOOR_CLEARANCE: f32 = 45.0;
+const TORPEDO_SPEED: f32 = 170.0;
+const AIM_CONE: f32 = 0.7;
+const SALVO_SPREAD: f32 = 0.2;
+const TENTACLES: usize = 6;
+const TENTACLE_SEGMENTS: usize = 10;
+const TENTACLE_SEGMENT_LENGTH: f32 = 9.0;
+const LASH_TIME: f32 = 1.2;
+const LASH_INTERVAL: [f32; 3] = [0.0, 3.0, 2.0];
+const MINE_INTERVAL: [f32; 3] = [0.0, 3.0, 2.2];
+const DYING_TIME: f32 = 2.0;
+const DYING_BLAST_INTERVAL: f32 = 0.25;
+// The mother sub takes turns with these
+const MINE_KINDS: [MineKind; 2] = [MineKind::Magnetic, MineKind::Proximity];
+
+struct WeakPoint {
+    offset: Vector2,
+    health: i32,
+    max_health: i32,
+    hit_dt: f32,
+}
+
+struct Tentacle {
+    root: Vector2,
+    // Angle off straight ahead when at rest
+    spread: f32,
+    wave_phase: f32,
+    // Seconds into a lash, and where it lashes to
+    lash: Option<f32>,
+    aim: f32,
+}
+
+impl Tentacle {
+    fn strength(&self) -> f32 {
+        self.lash
+            .map_or(0.0, |lash| (PI * lash / LASH_TIME).sin().max(0.0))
+    }
+
+    fn vertices(&self, pos: Vector2, age: f32) -> Vec<Vector2> {
+        let strength = self.strength();
+        let rest = PI + self.spread;
+        let base = rest + angle_diff(rest, self.aim) * strength;
+        let reach = 1.0 + 1.2 * strength;
+        let mut point = Vector2 {
+            x: pos.x + self.root.x,
+            y: pos.y + self.root.y,
+        };
+        let mut vertices = vec![];
+        for k in 0..=TENTACLE_SEGMENTS {
+            let t = k as f32 / TENTACLE_SEGMENTS as f32;
+            let angle = base + (age * 2.0 + self.wave_phase + k as f32 * 0.5).sin() * 0.4 * t;
+            let half_width = 5.0 * (1.0 - t) + 0.5;
+            vertices.push(Vector2 {
+                x: point.x - angle.sin() * half_width,
+                y: point.y + angle.cos() * half_width,
+            });
+            vertices.push(Vector2 {
+                x: point.x + angle.sin() * half_width,
+                y: point.y - angle.cos() * half_width,
+            });
+            point.x += angle.cos() * TENTACLE_SEGMENT_LENGTH * reach;
+            point.y += angle.sin() * TENTACLE_SEGMENT_LENGTH * reach;
+        }
+        vertices
+    }
+}
+
+/// Big enemy that holds up the scroll until all its weak points are destroyed.
+pub struct Boss {
+    kind: BossKind,
+    pos: Vector2,
+    prev_pos: Vector2,
+    weak_points: Vec<WeakPoint>,
+    tentacles: Vec<Tentacle>,
+    fire_dt: f32,
+    // Tentacle lash for the squid, mine drop for the mother sub
+    special_dt: f32,
+    age: f32,
+    mines_dropped: usize,
+    dying_dt: Option<f32>,
+    blast_dt: f32,
+    blasts: usize,
+    finished: bool,
+}
+
+impl Boss {
+    pub fn new(kind: BossKind, y: f32) -> Self {
+        let pos = Vector2 {
+            x: WINDOW_WIDTH as f32 + 60.0,
+            y,
+        };
+        let tentacles = match kind {
+            BossKind::Squid => (0..TENTACLES)
+                .map(|i| {
+                    let t = i as f32 / (TENTACLES - 1) as f32;
+                    Tentacle {
+                        root: Vector2 {
+                            x: -24.0,
+                            y: -15.0 + 30.0 * t,
+                        },
+                        spread: -0.5 + t,
+                        wave_phase: i as f32 * 1.3,
+                        lash: None,
+                        aim: PI,
+                    }
+                })
+                .collect(),
+            BossKind::MotherSub => vec![],
+        };
+        Self {
+            kind,
+            pos,
+            prev_pos: pos,
+            weak_points: kind
+                .weak_points()
+                .into_iter()
+                .map(|(offset, health)| WeakPoint {
+                    offset,
+                    health,
+                    max_health: health,
+                    hit_dt: 0.0,
+                })
+                .collect(),
+            tentacles,
+            fire_dt: kind.fire_interval(0),
+            special_dt: match kind {
+                BossKind::Squid => LASH_INTERVAL[1],
+                BossKind::MotherSub => MINE_INTERVAL[1],
+            },
+            age: 0.0,
+            mines_dropped: 0,
+            dying_dt: None,
+            blast_dt: 0.0,
+            blasts: 0,
+            finished: false,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn pos(&self) -> Vector2 {
+        self.pos
+    }
+
+    /// What is left of all the weak points together, 0.0 when destroyed.
+    pub fn health_fraction(&self) -> f32 {
+        let health: i32 = self.weak_points.iter().map(|w| w.health).sum();
+        let max_health: i32 = self.weak_points.iter().map(|w| w.max_health).sum();
+        health as f32 / max_health as f32
+    }
+
+    // Gets more aggressive as it takes damage
+    fn phase(&self) -> usize {
+        match self.health_fraction() {
+            f if f > 2.0 / 3.0 => 0,
+            f if f > 1.0 / 3.0 => 1,
+            _ => 2,
+        }
+    }
+
+    /// Screen positions of the weak points that are still intact.
+    pub fn weak_spots(&self) -> Vec<(usize, Vector2)> {
+        if self.dying_dt.is_some() {
+            return vec![];
+        }
+        self.weak_points
+            .iter()
+            .enumerate()
+            .filter(|(_, weak_point)| weak_point.health > 0)
+            .map(|(i, weak_point)| (i, self.pos + weak_point.offset))
+            .collect()
+    }
+
+    /// Everything the ship can run into and bullets go off against.
+    pub fn hit_shapes(&self) -> Vec<Vec<Vector2>> {
+        if self.dying_dt.is_some() {
+            return vec![];
+        }
+        let mut shapes = vec![self.body_vertices(self.pos)];
+        for tentacle in &self.tentacles {
+            shapes.push(tentacle.vertices(self.pos, self.age));
+        }
+        shapes
+    }
+
+    fn body_vertices(&self, pos: Vector2) -> Vec<Vector2> {
+        match self.kind {
+            BossKind::Squid => get_squid_vertices(pos.x, pos.y),
+            BossKind::MotherSub => get_mother_sub_vertices(pos.x, pos.y),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        dt: f32,
+        arena_x: f32,
+        ship: &Ship,
+        surface_verts: &SurfaceVerts,
+        seabed: &Seabed,
+        commands: &mut Commands,
+    ) {
+        if self.finished {
+            return;
+        }
+        self.prev_pos = self.pos;
+        self.age += dt;
+        for weak_point in &mut self.weak_points {
+            weak_point.hit_dt = (weak_point.hit_dt - dt).max(0.0);
+        }
+
+        if let Some(dying_dt) = &mut self.dying_dt {
+            *dying_dt += dt;
+            self.pos.y += dt * 20.0;
+            if *dying_dt >= DYING_TIME {
+                self.finished = true;
+                return;
+            }
+            self.blast_dt -= dt;
+            if self.blast_dt <= 0.0 {
+                self.blast_dt = DYING_BLAST_INTERVAL;
+                // Hops around the hull from one blast to the next
+                let body = self.body_vertices(self.pos);
+                let v = body[self.blasts * 3 % body.len()];
+                self.blasts += 1;
+                commands.push(Command::Explode(Blast::new(v, 30.0)));
+            }
+            return;
+        }
+
+        // Comes in from the right, then follows the ship up and down
+        let station_x = WINDOW_WIDTH as f32 - self.kind.station_margin();
+        let on_station = self.pos.x <= station_x;
+        if !on_station {
+            self.pos.x = (self.pos.x - ENTER_SPEED * dt).max(station_x);
+        }
+        let index = get_surface_verts_index(surface_verts, self.pos.x);
+        let y_min = surface_verts.layer_a[index].y + SURFACE_CLEARANCE;
+        let y_max = [0.0, 60.0, 120.0]
+            .iter()
+            .map(|dx| seabed.floor_y(self.pos.x + dx))
+            .fold(WINDOW_HEIGHT as f32, f32::min)
+            - FLOOR_CLEARANCE;
+        let step = TRACK_SPEED * dt;
+        self.pos.y += (ship.center().y - self.pos.y).clamp(-step, step);
+        self.pos.y = self.pos.y.max(y_min).min(y_max);
+
+        for tentacle in &mut self.tentacles {
+            if let Some(lash) = &mut tentacle.lash {
+                *lash += dt;
+                if *lash >= LASH_TIME {
+                    tentacle.lash = None;
+                }
+            }
+        }
+
+        if !on_station {
+            return;
+        }
+        let phase = self.phase();
+        self.fire_dt -= dt;
+        if self.fire_dt <= 0.0 {
+            self.fire_dt = self.kind.fire_interval(phase);
+            self.fire(phase, ship, commands);
+        }
+        if phase == 0 {
+            return;
+        }
+        self.special_dt -= dt;
+        if self.special_dt > 0.0 {
+            return;
+        }
+        match self.kind {
+            BossKind::Squid => {
+                self.special_dt = LASH_INTERVAL[phase];
+                self.lash(ship);
+            }
+            BossKind::MotherSub => {
+                self.special_dt = MINE_INTERVAL[phase];
+                let hatch = self.pos + self.weak_points[2].offset;
+                let kind = MINE_KINDS[self.mines_dropped % MINE_KINDS.len()];
+                self.mines_dropped += 1;
+                commands.push(Command::SpawnMine(Mine::new(kind, hatch, arena_x, ship)));
+            }
+        }
+    }
+
+    // Where its torpedoes come out
+    fn muzzle(&self) -> Vector2 {
+        let index = match self.kind {
+            BossKind::Squid => 2,
+            BossKind::MotherSub => 0,
+        };
+        self.pos + self.weak_points[index].offset
+    }
+
+    // One shot at the ship, a fan of three when it is down to its last third
+    fn fire(&self, phase: usize, ship: &Ship, commands: &mut Commands) {
+        let origin = self.muzzle();
+        let target = ship.center();
+        let angle = (target.y - origin.y).atan2(target.x - origin.x);
+        let heading = PI + angle_diff(PI, angle).clamp(-AIM_CONE, AIM_CONE);
+        let shots: &[f32] = if phase == 2 {
+            &[-SALVO_SPREAD, 0.0, SALVO_SPREAD]
+        } else {
+            &[0.0]
+        };
+        for offset in shots {
+            let heading = heading + offset;
+            commands.push(Command::SpawnBullet(Bullet::enemy_torpedo(
+                origin,
+                Vector2 {
+                    x: heading.cos() * TORPEDO_SPEED,
+                    y: heading.sin() * TORPEDO_SPEED,
+                },
+            )));
+        }
+    }
+
+    // The resting tentacle closest to the ship reaches out for it
+    fn lash(&mut self, ship: &Ship) {
+        let target = ship.center();
+        let pos = self.pos;
+        let tentacle = self
+            .tentacles
+            .iter_mut()
+            .filter(|tentacle| tentacle.lash.is_none())
+            .min_by(|a, b| {
+                let a_dist = (pos.y + a.root.y - target.y).abs();
+                let b_dist = (pos.y + b.root.y - target.y).abs();
+                a_dist.total_cmp(&b_dist)
+            });
+        if let Some(tentacle) = tentacle {
+            let root = pos + tentacle.root;
+            tentacle.aim = (target.y - root.y).atan2(target.x - root.x);
+            tentacle.lash = Some(0.0);
+        }
+    }
+
+    /// Damages the weak points in reach of a blast, returns true when that destroyed the boss.
+    pub fn blast(&mut self, pos: Vector2, radius: f32) -> bool {
+        if self.dying_dt.is_some() {
+            return false;
+        }
+        for weak_point in &mut self.weak_points {
+            let dist = (self.pos + weak_point.offset).distance_to(pos);
+            if weak_point.health > 0 && dist < radius + WEAK_POINT_RADIUS {
+                weak_point.health -= 1;
+                weak_point.hit_dt = HIT_FLASH;
+            }
+        }
+        if self.weak_points.iter().any(|w| w.health > 0) {
+            return false;
+        }
+        self.dying_dt = Some(0.0);
+        true
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_pos, self.pos, alpha);
+        let dying = self.dying_dt.is_some();
+        let color = match self.kind {
+            _ if dying => Color::DARKGRAY,
+            BossKind::Squid => Color::MAROON,
+            BossKind::MotherSub => Color::DARKPURPLE,
+        };
+        for tentacle in &self.tentacles {
+            d.draw_triangle_strip(&tentacle.vertices(pos, self.age), color);
+        }
+        d.draw_triangle_strip(&self.body_vertices(pos), color);
+        for weak_point in &self.weak_points {
+            let color = if weak_point.hit_dt > 0.0 {
+                Color::WHITE
+            } else if weak_point.health <= 0 {
+                Color::BLACK
+            } else {
+                match self.kind {
+                    BossKind::Squid => Color::YELLOW,
+                    BossKind::MotherSub => Color::ORANGE,
+                }
+            };
+            d.draw_circle(pos + weak_point.offset, WEAK_POINT_RADIUS, color);
+        }
+    }
+}
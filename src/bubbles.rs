@@ -1,13 +1,20 @@
 use std::f32;
 
+use rand::rngs::StdRng;
 use rand::Rng;
-use raylib::prelude::*;
 
+use crate::commands::Anchor;
 use crate::entity::{Entity, EntityManager};
+use crate::render::*;
 use crate::surface_verts::*;
+use crate::timestep::*;
+
+// Was 0.1 per frame at 60 FPS
+const SHRINK_SPEED: f32 = 6.0;
 
 struct Bubble {
     pos: Vector2,
+    prev_pos: Vector2,
     size: f32,
     color: Color,
     ax: f32,
@@ -20,7 +27,10 @@ struct Bubble {
 
 pub struct Bubbles {
     pos: Vector2,
+    anchor: Option<Anchor>,
     num: usize,
+    // all bubbles at once instead of a trail
+    burst: bool,
     els: Vec<Bubble>,
     dt: f32,
     next_dt: f32,
@@ -31,7 +41,9 @@ impl Bubbles {
     pub fn new(num: usize) -> Self {
         Self {
             pos: Vector2::zero(),
+            anchor: None,
             num,
+            burst: false,
             els: vec![],
             dt: 0.0,
             next_dt: 0.0,
@@ -39,17 +51,32 @@ impl Bubbles {
         }
     }
 
-    pub fn update(&mut self, dt: f32, surface_verts: &SurfaceVerts) {
+    pub fn burst(num: usize, pos: Vector2) -> Self {
+        Self {
+            pos,
+            burst: true,
+            ..Self::new(num)
+        }
+    }
+
+    pub fn attach(&mut self, anchor: Anchor) {
+        self.anchor = Some(anchor);
+    }
+
+    pub fn anchor(&self) -> Option<Anchor> {
+        self.anchor
+    }
+
+    pub fn update(&mut self, dt: f32, surface_verts: &SurfaceVerts, rng: &mut StdRng) {
         if self.finished {
             return;
         }
 
-        let mut rng = rand::thread_rng();
-
         let mut found_one = false;
         for el in &mut self.els {
+            el.prev_pos = el.pos;
             if el.dt0 == 0.0 {
-                el.size -= 0.1;
+                el.size -= dt * SHRINK_SPEED;
                 if el.size >= 1.0 {
                     found_one = true;
                 }
@@ -77,19 +104,26 @@ impl Bubbles {
         self.dt += dt;
         if self.els.len() < self.num {
             found_one = true;
-            if self.next_dt <= self.dt {
+            while self.els.len() < self.num && self.next_dt <= self.dt {
                 self.els.push(Bubble {
                     pos: self.pos,
+                    prev_pos: self.pos,
                     size: rng.gen_range(2.0..10.0),
                     color: Color::WHITE.alpha(0.5),
                     ax: 0.0,
                     ay: -40.0,
-                    vx: 0.0,
+                    vx: if self.burst {
+                        rng.gen_range(-80.0..80.0)
+                    } else {
+                        0.0
+                    },
                     vy: -100.0,
                     dt0: self.dt + rng.gen_range(0.0..2.0),
                     dt: 0.0,
                 });
-                self.next_dt = self.dt + 0.1 + rng.gen_range(0.0..0.1);
+                if !self.burst {
+                    self.next_dt = self.dt + 0.1 + rng.gen_range(0.0..0.1);
+                }
             }
         }
         if !found_one {
@@ -97,19 +131,18 @@ impl Bubbles {
         }
     }
 
-    pub fn draw<'d>(&self, mut d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
         for el in &self.els {
             if el.size > 0.0 {
-                d.draw_circle_v(el.pos, el.size, el.color);
+                d.draw_circle(lerp(el.prev_pos, el.pos, alpha), el.size, el.color);
             }
         }
-        d
     }
 }
 
 impl Entity for Bubbles {
-    fn draw<'d>(&self, d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        self.draw(d)
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
     }
 
     fn is_finished(&self) -> bool {
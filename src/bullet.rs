@@ -1,71 +1,332 @@
 use std::f32;
 
-use raylib::prelude::*;
-
+use crate::bubbles::*;
+use crate::collision::Collider;
+use crate::commands::*;
 use crate::consts::*;
-use crate::entity::{Entity, EntityManager};
+use crate::entity::{Entity, EntityId, EntityManager};
+use crate::explosion::Blast;
+use crate::fish::angle_diff;
+use crate::render::*;
+use crate::seabed::*;
+use crate::surface_verts::*;
+use crate::timestep::*;
+use crate::weapon::WeaponKind;
+
+pub fn get_bullet_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 { x: x, y: y - 5.0 }, // Top vertex
+        Vector2 { x: x - 5.0, y: y }, // Left vertex
+        Vector2 { x: x + 5.0, y: y }, // Right vertex
+        Vector2 { x: x, y: y + 5.0 }, // Bottom vertex
+    ]
+}
+
+pub fn get_depth_charge_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 {
+            x: x - 4.0,
+            y: y - 5.0,
+        }, // Top left
+        Vector2 {
+            x: x - 4.0,
+            y: y + 5.0,
+        }, // Bottom left
+        Vector2 {
+            x: x + 4.0,
+            y: y - 5.0,
+        }, // Top right
+        Vector2 {
+            x: x + 4.0,
+            y: y + 5.0,
+        }, // Bottom right
+    ]
+}
+
+fn get_vertices(kind: WeaponKind, x: f32, y: f32) -> Vec<Vector2> {
+    match kind {
+        WeaponKind::DepthCharge => get_depth_charge_vertices(x, y),
+        _ => get_bullet_vertices(x, y),
+    }
+}
+
+fn draw_bullet(
+    d: &mut dyn Renderer,
+    kind: WeaponKind,
+    hostile: bool,
+    bullet_x: f32,
+    bullet_y: f32,
+) {
+    if bullet_x > 0.0 {
+        let vertices = get_vertices(kind, bullet_x, bullet_y);
+        let color = match kind {
+            _ if hostile => Color::MAROON,
+            WeaponKind::Torpedo => Color::LIGHTGREEN,
+            WeaponKind::DepthCharge => Color::DARKGRAY,
+            WeaponKind::Spread => Color::YELLOW,
+            WeaponKind::Homing => Color::ORANGE,
+        };
+        d.draw_triangle_strip(&vertices, color);
+    }
+}
+
+// Underwater the velocity eases towards the cruise velocity
+const WATER_DRAG: f32 = 0.98;
+
+// Homing torpedoes only lock onto what is ahead of them
+const SEEK_CONE: f32 = 0.6;
+pub const SEEK_RANGE: f32 = 300.0;
+const TURN_RATE: f32 = 2.5;
+// Seconds the motor runs, after that the torpedo sinks
+const FUEL: f32 = 4.0;
+const SINK_SPEED: f32 = 20.0;
+const TRAIL_INTERVAL: f32 = 0.5;
+const DROPPED_CHARGE_SINK_SPEED: f32 = 80.0;
+
+/// Something a homing torpedo can lock onto.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+    Mine(EntityId),
+    Warship(EntityId),
+    EnemySub(EntityId),
+    // Index of a boss weak point
+    WeakPoint(usize),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Lock {
+    Seeking,
+    Locked(Target),
+    // The target is gone, the torpedo runs straight on
+    Lost,
+}
+
+struct Guidance {
+    lock: Lock,
+    range: f32,
+    heading: f32,
+    speed: f32,
+    fuel: f32,
+    trail_dt: f32,
+}
 
-fn draw_bullet(mut d: RaylibDrawHandle, bullet_x: f32, bullet_y: f32) -> RaylibDrawHandle {
-    let x = bullet_x;
-    let y = bullet_y;
+impl Guidance {
+    fn new(posd: Vector2, range: f32) -> Self {
+        Self {
+            lock: Lock::Seeking,
+            range,
+            heading: posd.y.atan2(posd.x),
+            speed: posd.length(),
+            fuel: FUEL,
+            trail_dt: 0.0,
+        }
+    }
 
-    if x > 0.0 {
-        let vertices = vec![
-            Vector2 { x: x, y: y - 5.0 }, // Top vertex
-            Vector2 { x: x - 5.0, y: y }, // Left vertex
-            Vector2 { x: x + 5.0, y: y }, // Right vertex
-            Vector2 { x: x, y: y + 5.0 }, // Bottom vertex
-        ];
+    fn acquire(&mut self, pos: Vector2, targets: &[(Target, Vector2)]) {
+        let mut nearest: Option<(Target, f32)> = None;
+        for (target, target_pos) in targets {
+            let dist = pos.distance_to(*target_pos);
+            let angle = (target_pos.y - pos.y).atan2(target_pos.x - pos.x);
+            if dist > self.range || angle_diff(self.heading, angle).abs() > SEEK_CONE {
+                continue;
+            }
+            if nearest.is_none_or(|(_, nearest_dist)| dist < nearest_dist) {
+                nearest = Some((*target, dist));
+            }
+        }
+        if let Some((target, _)) = nearest {
+            self.lock = Lock::Locked(target);
+        }
+    }
 
-        d.draw_triangle_strip(&vertices, Color::LIGHTGREEN);
+    // Turns towards the target and returns the new cruise velocity
+    fn steer(&mut self, dt: f32, pos: Vector2, targets: &[(Target, Vector2)]) -> Vector2 {
+        self.fuel -= dt;
+        if self.fuel <= 0.0 {
+            return Vector2 {
+                x: 0.0,
+                y: SINK_SPEED,
+            };
+        }
+        if self.lock == Lock::Seeking {
+            self.acquire(pos, targets);
+        }
+        if let Lock::Locked(target) = self.lock {
+            match targets.iter().find(|(t, _)| *t == target) {
+                Some((_, target_pos)) => {
+                    let angle = (target_pos.y - pos.y).atan2(target_pos.x - pos.x);
+                    let diff = angle_diff(self.heading, angle);
+                    self.heading += diff.clamp(-TURN_RATE * dt, TURN_RATE * dt);
+                }
+                None => self.lock = Lock::Lost,
+            }
+        }
+        Vector2 {
+            x: self.heading.cos() * self.speed,
+            y: self.heading.sin() * self.speed,
+        }
     }
-    d
 }
 
 pub struct Bullet {
+    kind: WeaponKind,
     pos: Vector2,
+    prev_pos: Vector2,
     posd: Vector2,
-    vy: f32,
+    // What the motor keeps it at underwater, a depth charge just sinks
+    cruise: Vector2,
+    guidance: Option<Guidance>,
+    // Fired by the enemy, it hurts the ship instead of mines and fish
+    hostile: bool,
+    // Depth where a hostile depth charge goes off by itself
+    fuse_y: f32,
+    in_air: bool,
     finished: bool,
 }
 
 impl Bullet {
-    pub fn new(pos: Vector2) -> Self {
+    /// `posd` is the launch velocity.
+    pub fn new(kind: WeaponKind, pos: Vector2, posd: Vector2) -> Self {
         Self {
+            kind,
             pos,
-            posd: Vector2 { x: 300.0, y: 10.0 },
-            vy: 1.03,
+            prev_pos: pos,
+            posd,
+            cruise: match kind {
+                WeaponKind::DepthCharge => Vector2 { x: 0.0, y: posd.y },
+                _ => posd,
+            },
+            guidance: if kind == WeaponKind::Homing {
+                Some(Guidance::new(posd, SEEK_RANGE))
+            } else {
+                None
+            },
+            hostile: false,
+            fuse_y: f32::INFINITY,
+            in_air: false,
             finished: false,
         }
     }
 
-    // TODO: use surface_verts
-    pub fn update(&mut self, dt: f32) {
+    /// Depth charge dropped by a warship, goes off when it reaches `fuse_y`.
+    pub fn dropped_charge(pos: Vector2, fuse_y: f32) -> Self {
+        Self {
+            hostile: true,
+            fuse_y,
+            ..Self::new(
+                WeaponKind::DepthCharge,
+                pos,
+                Vector2 {
+                    x: 0.0,
+                    y: DROPPED_CHARGE_SINK_SPEED,
+                },
+            )
+        }
+    }
+
+    /// Homing torpedo whose sonar finds targets out to `seek_range`.
+    pub fn homing(pos: Vector2, posd: Vector2, seek_range: f32) -> Self {
+        Self {
+            guidance: Some(Guidance::new(posd, seek_range)),
+            ..Self::new(WeaponKind::Homing, pos, posd)
+        }
+    }
+
+    /// Torpedo fired back by an enemy submarine.
+    pub fn enemy_torpedo(pos: Vector2, posd: Vector2) -> Self {
+        Self {
+            hostile: true,
+            ..Self::new(WeaponKind::Torpedo, pos, posd)
+        }
+    }
+
+    pub fn is_hostile(&self) -> bool {
+        self.hostile
+    }
+
+    pub fn pos(&self) -> Vector2 {
+        self.pos
+    }
+
+    pub fn vel(&self) -> Vector2 {
+        self.posd
+    }
+
+    pub fn update(
+        &mut self,
+        dt: f32,
+        id: EntityId,
+        targets: &[(Target, Vector2)],
+        surface_verts: &SurfaceVerts,
+        seabed: &Seabed,
+        commands: &mut Commands,
+    ) {
         if self.finished {
             return;
         }
+        self.prev_pos = self.pos;
 
-        self.pos.x = self.pos.x + dt * self.posd.x;
-        self.pos.y = self.pos.y + dt * self.posd.y;
-        self.vy *= 0.999;
-        self.posd.y *= self.vy;
-        if self.pos.x < 0.0
-            || self.pos.y < 0.0
-            || self.pos.x > WINDOW_WIDTH as f32
-            || self.pos.y >= WINDOW_HEIGHT as f32
-        {
+        self.pos.x += dt * self.posd.x;
+        self.pos.y += dt * self.posd.y;
+        if self.pos.x < 0.0 || self.pos.x > WINDOW_WIDTH as f32 {
             self.finished = true;
+            return;
+        }
+
+        // Out of the water it flies in an arc until it falls back in
+        let index = get_surface_verts_index(surface_verts, self.pos.x);
+        let in_air = self.pos.y < surface_verts.layer_a[index].y;
+        if in_air != self.in_air {
+            self.in_air = in_air;
+            commands.push(Command::Splash(self.pos));
+        }
+        if in_air {
+            self.posd.y += GRAVITY * dt;
+        } else {
+            if let Some(guidance) = &mut self.guidance {
+                self.cruise = guidance.steer(dt, self.pos, targets);
+                if guidance.fuel > 0.0 {
+                    guidance.trail_dt -= dt;
+                    if guidance.trail_dt <= 0.0 {
+                        commands.push(Command::AttachBubbles(Bubbles::new(3), Anchor::Bullet(id)));
+                        guidance.trail_dt = TRAIL_INTERVAL;
+                    }
+                }
+            }
+            let drag = per_frame(WATER_DRAG, dt);
+            self.posd.x = self.cruise.x + (self.posd.x - self.cruise.x) * drag;
+            self.posd.y = self.cruise.y + (self.posd.y - self.cruise.y) * drag;
+        }
+
+        if self.pos.y >= WINDOW_HEIGHT as f32
+            || self.pos.y >= self.fuse_y
+            || seabed.is_rock(self.pos.x, self.pos.y)
+        {
+            self.detonate(commands);
         }
     }
 
-    pub fn draw<'d>(&self, mut d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        draw_bullet(d, self.pos.x, self.pos.y)
+    /// Goes off where it is.
+    pub fn detonate(&mut self, commands: &mut Commands) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        commands.push(Command::Explode(Blast::new(
+            self.pos,
+            self.kind.blast_radius(),
+        )));
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_pos, self.pos, alpha);
+        draw_bullet(d, self.kind, self.hostile, pos.x, pos.y)
     }
 }
 
 impl Entity for Bullet {
-    fn draw<'d>(&self, d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        self.draw(d)
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
     }
 
     fn is_finished(&self) -> bool {
@@ -77,4 +338,10 @@ impl Entity for Bullet {
     }
 }
 
+impl Collider for Bullet {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        get_vertices(self.kind, self.pos.x, self.pos.y)
+    }
+}
+
 pub type BulletManager = EntityManager<Bullet>;
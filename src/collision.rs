@@ -0,0 +1,269 @@
+use crate::boss::*;
+use crate::bullet::*;
+use crate::enemy_sub::*;
+use crate::entity::{Entity, EntityId, EntityManager};
+use crate::fish_swarm::*;
+use crate::mine::*;
+use crate::render::*;
+use crate::ship::*;
+use crate::warship::*;
+
+/// Anything that can be hit exposes the triangle strip it is drawn with.
+pub trait Collider {
+    fn hit_shape(&self) -> Vec<Vector2>;
+}
+
+pub enum Contact {
+    ShipMine(EntityId),
+    BulletMine(EntityId, EntityId),
+    BulletFish(EntityId, EntityId, EntityId),
+    ShipFish(EntityId, EntityId),
+    BulletWarship(EntityId, EntityId),
+    BulletEnemySub(EntityId, EntityId),
+    ShipEnemySub(EntityId),
+    BulletBoss(EntityId),
+    ShipBoss,
+    // A hostile bullet reached the ship
+    ShipBullet(EntityId),
+}
+
+fn bounds(strip: &[Vector2]) -> (Vector2, Vector2) {
+    let mut min = Vector2 {
+        x: f32::MAX,
+        y: f32::MAX,
+    };
+    let mut max = Vector2 {
+        x: f32::MIN,
+        y: f32::MIN,
+    };
+    for v in strip {
+        min.x = min.x.min(v.x);
+        min.y = min.y.min(v.y);
+        max.x = max.x.max(v.x);
+        max.y = max.y.max(v.y);
+    }
+    (min, max)
+}
+
+fn project(tri: &[Vector2], axis: Vector2) -> (f32, f32) {
+    let mut min = f32::MAX;
+    let mut max = f32::MIN;
+    for v in tri {
+        let p = v.x * axis.x + v.y * axis.y;
+        min = min.min(p);
+        max = max.max(p);
+    }
+    (min, max)
+}
+
+// Separating axis test, the edge normals of both triangles are the candidate axes
+fn triangles_overlap(a: &[Vector2], b: &[Vector2]) -> bool {
+    for tri in [a, b] {
+        for i in 0..3 {
+            let v0 = tri[i];
+            let v1 = tri[(i + 1) % 3];
+            let axis = Vector2 {
+                x: v0.y - v1.y,
+                y: v1.x - v0.x,
+            };
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            if a_max < b_min || b_max < a_min {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Both arguments are triangle strips as passed to `draw_triangle_strip`.
+pub fn strips_overlap(a: &[Vector2], b: &[Vector2]) -> bool {
+    if a.len() < 3 || b.len() < 3 {
+        return false;
+    }
+    let (a_min, a_max) = bounds(a);
+    let (b_min, b_max) = bounds(b);
+    if a_max.x < b_min.x || b_max.x < a_min.x || a_max.y < b_min.y || b_max.y < a_min.y {
+        return false;
+    }
+    a.windows(3)
+        .any(|tri_a| b.windows(3).any(|tri_b| triangles_overlap(tri_a, tri_b)))
+}
+
+pub fn detect_contacts(
+    ship: &Ship,
+    bullet_manager: &BulletManager,
+    mine_manager: &MineManager,
+    fish_swarm_manager: &FishSwarmManager,
+    warship_manager: &WarshipManager,
+    enemy_sub_manager: &EnemySubManager,
+    boss: Option<&Boss>,
+) -> Vec<Contact> {
+    let mut contacts: Vec<Contact> = vec![];
+    let ship_shape = ship.hit_shape();
+    let mut bullet_shapes: Vec<(EntityId, Vec<Vector2>)> = vec![];
+    for (bullet_id, bullet) in bullet_manager
+        .iter()
+        .filter(|(_, bullet)| !bullet.is_finished())
+    {
+        let bullet_shape = bullet.hit_shape();
+        if !bullet.is_hostile() {
+            bullet_shapes.push((bullet_id, bullet_shape));
+        } else if strips_overlap(&ship_shape, &bullet_shape) {
+            contacts.push(Contact::ShipBullet(bullet_id));
+        }
+    }
+
+    for (warship_id, warship) in warship_manager
+        .iter()
+        .filter(|(_, warship)| !warship.is_finished() && !warship.is_sinking())
+    {
+        let warship_shape = warship.hit_shape();
+        for (bullet_id, bullet_shape) in &bullet_shapes {
+            if strips_overlap(bullet_shape, &warship_shape) {
+                contacts.push(Contact::BulletWarship(*bullet_id, warship_id));
+            }
+        }
+    }
+
+    if let Some(boss) = boss {
+        let boss_shapes = boss.hit_shapes();
+        if boss_shapes
+            .iter()
+            .any(|shape| strips_overlap(&ship_shape, shape))
+        {
+            contacts.push(Contact::ShipBoss);
+        }
+        for (bullet_id, bullet_shape) in &bullet_shapes {
+            if boss_shapes
+                .iter()
+                .any(|shape| strips_overlap(bullet_shape, shape))
+            {
+                contacts.push(Contact::BulletBoss(*bullet_id));
+            }
+        }
+    }
+
+    for (sub_id, enemy_sub) in enemy_sub_manager
+        .iter()
+        .filter(|(_, enemy_sub)| !enemy_sub.is_finished())
+    {
+        let sub_shape = enemy_sub.hit_shape();
+        if strips_overlap(&ship_shape, &sub_shape) {
+            contacts.push(Contact::ShipEnemySub(sub_id));
+        }
+        for (bullet_id, bullet_shape) in &bullet_shapes {
+            if strips_overlap(bullet_shape, &sub_shape) {
+                contacts.push(Contact::BulletEnemySub(*bullet_id, sub_id));
+            }
+        }
+    }
+
+    for (mine_id, mine) in mine_manager.iter().filter(|(_, mine)| !mine.is_finished()) {
+        let mine_shape = mine.hit_shape();
+        if strips_overlap(&ship_shape, &mine_shape) {
+            contacts.push(Contact::ShipMine(mine_id));
+        }
+        for (bullet_id, bullet_shape) in &bullet_shapes {
+            if strips_overlap(bullet_shape, &mine_shape) {
+                contacts.push(Contact::BulletMine(*bullet_id, mine_id));
+            }
+        }
+    }
+
+    for (swarm_id, fish_swarm) in fish_swarm_manager.iter() {
+        for (fish_id, fish) in fish_swarm
+            .iter_fish()
+            .filter(|(_, fish)| !fish.is_finished())
+        {
+            let fish_shape = fish.hit_shape();
+            if strips_overlap(&ship_shape, &fish_shape) {
+                contacts.push(Contact::ShipFish(swarm_id, fish_id));
+            }
+            for (bullet_id, bullet_shape) in &bullet_shapes {
+                if strips_overlap(bullet_shape, &fish_shape) {
+                    contacts.push(Contact::BulletFish(*bullet_id, swarm_id, fish_id));
+                }
+            }
+        }
+    }
+
+    contacts
+}
+
+/// Pickups, like treasure or power-ups, the ship is touching.
+pub fn detect_pickups<T: Entity + Collider>(
+    ship: &Ship,
+    manager: &EntityManager<T>,
+) -> Vec<EntityId> {
+    let ship_shape = ship.hit_shape();
+    manager
+        .iter()
+        .filter(|(_, pickup)| !pickup.is_finished())
+        .filter(|(_, pickup)| strips_overlap(&ship_shape, &pickup.hit_shape()))
+        .map(|(pickup_id, _)| pickup_id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Vec<Vector2> {
+        vec![
+            Vector2 { x, y },
+            Vector2 { x, y: y + size },
+            Vector2 { x: x + size, y },
+            Vector2 {
+                x: x + size,
+                y: y + size,
+            },
+        ]
+    }
+
+    #[test]
+    fn overlapping_strips_touch() {
+        assert!(strips_overlap(
+            &square(0.0, 0.0, 10.0),
+            &square(5.0, 5.0, 10.0)
+        ));
+    }
+
+    #[test]
+    fn strip_inside_another_touches() {
+        assert!(strips_overlap(
+            &square(0.0, 0.0, 30.0),
+            &square(10.0, 10.0, 5.0)
+        ));
+    }
+
+    #[test]
+    fn strips_apart_do_not_touch() {
+        assert!(!strips_overlap(
+            &square(0.0, 0.0, 10.0),
+            &square(20.0, 0.0, 10.0)
+        ));
+    }
+
+    // The bounding boxes overlap, the triangles do not
+    #[test]
+    fn diagonal_neighbours_do_not_touch() {
+        let a = vec![
+            Vector2 { x: 0.0, y: 0.0 },
+            Vector2 { x: 10.0, y: 0.0 },
+            Vector2 { x: 0.0, y: 10.0 },
+        ];
+        let b = vec![
+            Vector2 { x: 10.0, y: 10.0 },
+            Vector2 { x: 10.0, y: 6.0 },
+            Vector2 { x: 6.0, y: 10.0 },
+        ];
+        assert!(!strips_overlap(&a, &b));
+    }
+
+    #[test]
+    fn too_few_points_never_touch() {
+        let line = vec![Vector2 { x: 0.0, y: 0.0 }, Vector2 { x: 10.0, y: 10.0 }];
+        assert!(!strips_overlap(&line, &square(0.0, 0.0, 10.0)));
+    }
+}
@@ -0,0 +1,46 @@
+use crate::bubbles::*;
+use crate::bullet::*;
+use crate::entity::EntityId;
+use crate::explosion::Blast;
+use crate::mine::*;
+use crate::render::*;
+use crate::ship::Section;
+
+/// What an attached bubble trail follows around.
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    Ship,
+    ShipSection(Section),
+    Mine(EntityId),
+    Bullet(EntityId),
+}
+
+pub enum Command {
+    SpawnBullet(Bullet),
+    SpawnMine(Mine),
+    AttachBubbles(Bubbles, Anchor),
+    DespawnBullet(EntityId),
+    // Spawns an explosion and a burst of bubbles
+    Explode(Blast),
+    Splash(Vector2),
+}
+
+/// Entities push requests here while they update, the world applies them
+/// once per tick after everything has moved.
+pub struct Commands {
+    queue: Vec<Command>,
+}
+
+impl Commands {
+    pub fn new() -> Self {
+        Self { queue: vec![] }
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.queue.push(command);
+    }
+
+    pub fn take(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.queue)
+    }
+}
@@ -2,3 +2,6 @@ pub static WINDOW_WIDTH: i32 = 640;
 pub static WINDOW_HEIGHT: i32 = 480;
 
 pub static SURFACE_WIDTH: i32 = 100;
+
+// Pulls everything that leaves the water back down
+pub static GRAVITY: f32 = 400.0;
@@ -0,0 +1,326 @@
+use std::f32::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::bullet::*;
+use crate::collision::Collider;
+use crate::commands::*;
+use crate::consts::*;
+use crate::entity::{Entity, EntityManager};
+use crate::explosion::Blast;
+use crate::fish::angle_diff;
+use crate::render::*;
+use crate::seabed::*;
+use crate::ship::*;
+use crate::surface_verts::*;
+use crate::timestep::*;
+
+/// The player's hull turned around, nose and cannon on the left.
+pub fn get_enemy_sub_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    get_ship_vertices(x, y)
+        .into_iter()
+        .map(|v| Vector2 {
+            x: x * 2.0 + 45.0 - v.x,
+            y: v.y,
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Profile {
+    // Cruises along at its depth and fires at what it sees
+    Patrol,
+    // Lies still near the floor until the ship comes close
+    Ambush,
+    // Keeps in front of the ship once it has seen it
+    Chase,
+}
+
+impl Profile {
+    pub fn random(rng: &mut StdRng) -> Self {
+        match rng.gen_range(0..3) {
+            0 => Profile::Patrol,
+            1 => Profile::Ambush,
+            _ => Profile::Chase,
+        }
+    }
+
+    // Seconds between two torpedoes
+    fn fire_interval(self) -> f32 {
+        match self {
+            Profile::Patrol => 2.5,
+            Profile::Ambush => 1.2,
+            Profile::Chase => 1.8,
+        }
+    }
+}
+
+const HEALTH: i32 = 3;
+const BLAST_RADIUS: f32 = 50.0;
+const PATROL_SPEED: f32 = 30.0;
+const PATROL_SWAY: f32 = 30.0;
+const CHASE_SPEED: f32 = 150.0;
+// Screen distance kept in front of the ship while chasing
+const CHASE_DISTANCE: f32 = 220.0;
+const CLIMB_SPEED: f32 = 60.0;
+const SIGHT_RANGE: f32 = 350.0;
+const AMBUSH_RANGE: f32 = 200.0;
+// Seconds a chase goes on after losing sight of the ship
+const MEMORY: f32 = 3.0;
+const TORPEDO_SPEED: f32 = 180.0;
+// How far off straight ahead it can aim
+const AIM_CONE: f32 = 0.5;
+const EVADE_RANGE: f32 = 150.0;
+const EVADE_SPEED: f32 = 120.0;
+const EVADE_TIME: f32 = 0.4;
+// Needs a moment before it can dodge again
+const EVADE_COOLDOWN: f32 = 1.0;
+const SURFACE_CLEARANCE: f32 = 40.0;
+const HIT_FLASH: f32 = 0.15;
+
+// Looks for rock along the way in small steps
+fn line_of_sight(seabed: &Seabed, from: Vector2, to: Vector2) -> bool {
+    let steps = (from.distance_to(to) / 8.0).ceil() as i32;
+    (1..steps).all(|i| {
+        let t = i as f32 / steps as f32;
+        !seabed.is_rock(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t)
+    })
+}
+
+/// Hostile submarine that hunts the ship and shoots back.
+pub struct EnemySub {
+    profile: Profile,
+    // x in arena coordinates, y on screen
+    pos: Vector2,
+    cruise_y: f32,
+    vel_y: f32,
+    health: i32,
+    // Seconds since the ship was last in sight, None before it was ever seen
+    last_seen: Option<f32>,
+    fire_dt: f32,
+    evade_dt: f32,
+    evade_dir: f32,
+    hit_dt: f32,
+    age: f32,
+    arena_x: f32,
+    prev_screen_pos: Vector2,
+    finished: bool,
+}
+
+impl EnemySub {
+    /// `x` is in arena coordinates, like the surface segments.
+    pub fn new(profile: Profile, x: f32, y: f32, arena_x: f32) -> Self {
+        Self {
+            profile,
+            pos: Vector2 { x, y },
+            cruise_y: y,
+            vel_y: 0.0,
+            health: HEALTH,
+            last_seen: None,
+            fire_dt: profile.fire_interval(),
+            evade_dt: -EVADE_COOLDOWN,
+            evade_dir: 0.0,
+            hit_dt: 0.0,
+            age: 0.0,
+            arena_x,
+            prev_screen_pos: Vector2 { x: arena_x + x, y },
+            finished: false,
+        }
+    }
+
+    pub fn screen_pos(&self) -> Vector2 {
+        Vector2 {
+            x: self.arena_x + self.pos.x,
+            y: self.pos.y,
+        }
+    }
+
+    pub fn center(&self) -> Vector2 {
+        Vector2 {
+            x: self.arena_x + self.pos.x + 22.5,
+            y: self.pos.y,
+        }
+    }
+
+    fn is_alerted(&self) -> bool {
+        self.last_seen.is_some_and(|last_seen| last_seen < MEMORY)
+    }
+
+    /// Dives away from a torpedo of the ship that is heading its way.
+    pub fn dodge(&mut self, bullet_pos: Vector2, bullet_vel: Vector2) {
+        let center = self.center();
+        let dx = center.x - bullet_pos.x;
+        if self.evade_dt > -EVADE_COOLDOWN
+            || bullet_vel.x <= 0.0
+            || !(0.0..=EVADE_RANGE).contains(&dx)
+        {
+            return;
+        }
+        let arrival_y = bullet_pos.y + bullet_vel.y * dx / bullet_vel.x;
+        if (arrival_y - center.y).abs() > 15.0 {
+            return;
+        }
+        self.evade_dt = EVADE_TIME;
+        self.evade_dir = if center.y < arrival_y { -1.0 } else { 1.0 };
+    }
+
+    pub fn update(
+        &mut self,
+        dt: f32,
+        arena_x: f32,
+        ship: &Ship,
+        surface_verts: &SurfaceVerts,
+        seabed: &Seabed,
+        commands: &mut Commands,
+    ) {
+        if self.finished {
+            return;
+        }
+        self.prev_screen_pos = self.screen_pos();
+        self.arena_x = arena_x;
+        self.age += dt;
+        self.hit_dt = (self.hit_dt - dt).max(0.0);
+
+        let x = arena_x + self.pos.x;
+        if x < -50.0 {
+            self.finished = true;
+            return;
+        }
+
+        let center = self.center();
+        let target = ship.center();
+        let on_screen = (0.0..WINDOW_WIDTH as f32).contains(&x);
+        let range = match self.profile {
+            Profile::Ambush if self.last_seen.is_none() => AMBUSH_RANGE,
+            _ => SIGHT_RANGE,
+        };
+        let sees_ship = on_screen
+            && center.distance_to(target) < range
+            && line_of_sight(seabed, center, target);
+        self.last_seen = match self.last_seen {
+            _ if sees_ship => Some(0.0),
+            Some(last_seen) => Some(last_seen + dt),
+            None => None,
+        };
+
+        // Patrols keep their course, the others go after the ship once they know about it
+        let hunting = self.profile != Profile::Patrol && self.is_alerted();
+        let target_y = if hunting {
+            let chase_x = target.x + CHASE_DISTANCE;
+            let step = CHASE_SPEED * dt;
+            self.pos.x += (chase_x - center.x).clamp(-step, step);
+            ship.pos.y
+        } else {
+            match self.profile {
+                Profile::Ambush => seabed.floor_y(center.x) - 20.0,
+                _ => {
+                    self.pos.x -= PATROL_SPEED * dt;
+                    self.cruise_y + (self.age * 0.8).sin() * PATROL_SWAY
+                }
+            }
+        };
+
+        self.evade_dt = (self.evade_dt - dt).max(-EVADE_COOLDOWN);
+        if self.evade_dt > 0.0 {
+            self.vel_y = self.evade_dir * EVADE_SPEED;
+        } else {
+            let smoothing = per_frame(0.9, dt);
+            let climb = (target_y - self.pos.y).clamp(-CLIMB_SPEED, CLIMB_SPEED);
+            self.vel_y = self.vel_y * smoothing + climb * (1.0 - smoothing);
+        }
+        self.pos.y += self.vel_y * dt;
+
+        let index = get_surface_verts_index(surface_verts, arena_x + self.pos.x);
+        self.pos.y = self
+            .pos
+            .y
+            .max(surface_verts.layer_a[index].y + SURFACE_CLEARANCE);
+        let mut push = 0.0f32;
+        for v in get_enemy_sub_vertices(arena_x + self.pos.x, self.pos.y) {
+            let dy = seabed.push_out(v.x, v.y, 0.0) - v.y;
+            if dy.abs() > push.abs() {
+                push = dy;
+            }
+        }
+        self.pos.y += push;
+
+        self.fire_dt -= dt;
+        if sees_ship && target.x < center.x && self.fire_dt <= 0.0 {
+            self.fire_dt = self.profile.fire_interval();
+            self.fire(target, commands);
+        }
+    }
+
+    fn fire(&self, target: Vector2, commands: &mut Commands) {
+        let pos = Vector2 {
+            x: self.arena_x + self.pos.x,
+            y: self.pos.y + 5.0,
+        };
+        let angle = (target.y - pos.y).atan2(target.x - pos.x);
+        let heading = PI + angle_diff(PI, angle).clamp(-AIM_CONE, AIM_CONE);
+        commands.push(Command::SpawnBullet(Bullet::enemy_torpedo(
+            pos,
+            Vector2 {
+                x: heading.cos() * TORPEDO_SPEED,
+                y: heading.sin() * TORPEDO_SPEED,
+            },
+        )));
+    }
+
+    /// Returns true when this hit sank it.
+    pub fn hit(&mut self, commands: &mut Commands) -> bool {
+        if self.finished {
+            return false;
+        }
+        // Being shot at gives the ship away
+        self.last_seen = Some(0.0);
+        self.hit_dt = HIT_FLASH;
+        self.health -= 1;
+        if self.health > 0 {
+            return false;
+        }
+        self.finished = true;
+        commands.push(Command::Explode(Blast::new(self.center(), BLAST_RADIUS)));
+        true
+    }
+
+    /// Rammed by the ship, it goes down at once.
+    pub fn wreck(&mut self, commands: &mut Commands) -> bool {
+        self.health = self.health.min(1);
+        self.hit(commands)
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_screen_pos, self.screen_pos(), alpha);
+        let color = if self.hit_dt > 0.0 {
+            Color::RED
+        } else if self.is_alerted() {
+            Color::PURPLE
+        } else {
+            Color::DARKPURPLE
+        };
+        d.draw_triangle_strip(&get_enemy_sub_vertices(pos.x, pos.y), color);
+    }
+}
+
+impl Entity for EnemySub {
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn set_pos(&mut self, _pos: Vector2) {}
+}
+
+impl Collider for EnemySub {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        let pos = self.screen_pos();
+        get_enemy_sub_vertices(pos.x, pos.y)
+    }
+}
+
+pub type EnemySubManager = EntityManager<EnemySub>;
@@ -1,73 +1,206 @@
-use std::collections::HashMap;
-
-use raylib::prelude::*;
+use crate::render::*;
 
 pub trait Entity {
-    fn draw<'a>(&self, d: RaylibDrawHandle<'a>) -> RaylibDrawHandle<'a>;
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32);
     fn is_finished(&self) -> bool;
     fn set_pos(&mut self, pos: Vector2);
 }
 
+/// Handle to an entity in an `EntityManager`. A slot gets a new generation every
+/// time it is reused, so an old handle never refers to a newer entity.
+/// `EntityId::default()` refers to nothing.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct EntityId {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    entity: Option<T>,
+}
+
 pub struct EntityManager<T: Entity> {
-    entities: HashMap<usize, T>,
-    last_id: usize,
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    // Live slots in insertion order
+    order: Vec<u32>,
 }
 
 impl<T: Entity> EntityManager<T> {
     pub fn new() -> Self {
         Self {
-            entities: HashMap::new(),
-            last_id: 0,
+            slots: vec![],
+            free: vec![],
+            order: vec![],
+        }
+    }
+
+    pub fn insert(&mut self, entity: T) -> EntityId {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entity: None,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.generation += 1;
+        slot.entity = Some(entity);
+        self.order.push(index);
+        EntityId {
+            index,
+            generation: slot.generation,
         }
     }
 
-    pub fn insert(&mut self, entity: T) -> usize {
-        self.last_id += 1;
-        self.entities.insert(self.last_id, entity);
-        self.last_id
+    pub fn remove(&mut self, id: EntityId) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let entity = slot.entity.take()?;
+        self.free.push(id.index);
+        self.order.retain(|index| *index != id.index);
+        Some(entity)
     }
 
-    pub fn head(&mut self) -> Option<&mut T> {
-        self.entities.values_mut().next()
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.entity.as_ref())
     }
 
-    // Unused
-    // pub fn remove(&mut self, id: usize) {
-    //     self.entities.remove(&id);
-    // }
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.entity.as_mut())
+    }
 
+    /// The oldest entity.
+    pub fn head(&self) -> Option<&T> {
+        self.order
+            .first()
+            .and_then(|index| self.slots[*index as usize].entity.as_ref())
+    }
+
+    /// Entities in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.order.iter().filter_map(|index| {
+            let slot = &self.slots[*index as usize];
+            slot.entity.as_ref().map(|entity| {
+                (
+                    EntityId {
+                        index: *index,
+                        generation: slot.generation,
+                    },
+                    entity,
+                )
+            })
+        })
+    }
+
+    /// Entities in insertion order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+        let mut slots: Vec<Option<&mut Slot<T>>> = self.slots.iter_mut().map(Some).collect();
+        self.order.iter().filter_map(move |index| {
+            let slot = slots[*index as usize].take()?;
+            let generation = slot.generation;
+            slot.entity.as_mut().map(|entity| {
+                (
+                    EntityId {
+                        index: *index,
+                        generation,
+                    },
+                    entity,
+                )
+            })
+        })
+    }
+
+    /// Calls `closure` with every entity, its position in insertion order and its id,
+    /// then drops finished entities. Returns true when none are left.
     pub fn update<F>(&mut self, mut closure: F) -> bool
     where
-        F: FnMut(&mut T, i32),
+        F: FnMut(&mut T, usize, EntityId),
     {
-        let mut i: i32 = 0;
-        for entity in self.entities.values_mut() {
-            closure(entity, i);
-            i += 1;
+        for (i, index) in self.order.iter().enumerate() {
+            let slot = &mut self.slots[*index as usize];
+            let id = EntityId {
+                index: *index,
+                generation: slot.generation,
+            };
+            if let Some(entity) = slot.entity.as_mut() {
+                closure(entity, i, id);
+            }
         }
 
-        self.entities.retain(|_, entity| !entity.is_finished());
-        self.entities.len() == 0
+        let slots = &mut self.slots;
+        let free = &mut self.free;
+        self.order.retain(|index| {
+            let slot = &mut slots[*index as usize];
+            if slot
+                .entity
+                .as_ref()
+                .is_none_or(|entity| entity.is_finished())
+            {
+                slot.entity = None;
+                free.push(*index);
+                return false;
+            }
+            true
+        });
+        self.order.is_empty()
     }
 
-    pub fn draw<'d>(&self, mut d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        for entity in self.entities.values() {
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        for (_, entity) in self.iter() {
             if !entity.is_finished() {
-                d = entity.draw(d);
+                entity.draw(d, alpha);
             }
         }
-        d
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn set_pos(&mut self, id: usize, pos: Vector2) {
-        if let Some(entity) = self.entities.get_mut(&id) {
-            entity.set_pos(pos);
+    struct Dummy(i32);
+
+    impl Entity for Dummy {
+        fn draw(&self, _d: &mut dyn Renderer, _alpha: f32) {}
+
+        fn is_finished(&self) -> bool {
+            false
         }
+
+        fn set_pos(&mut self, _pos: Vector2) {}
+    }
+
+    #[test]
+    fn stale_id_does_not_reach_the_reused_slot() {
+        let mut manager = EntityManager::new();
+        let old_id = manager.insert(Dummy(1));
+        assert!(manager.remove(old_id).is_some());
+        let new_id = manager.insert(Dummy(2));
+
+        assert_ne!(old_id, new_id);
+        assert!(manager.get(old_id).is_none());
+        assert!(manager.get_mut(old_id).is_none());
+        assert!(manager.remove(old_id).is_none());
+        assert_eq!(manager.get(new_id).map(|dummy| dummy.0), Some(2));
     }
 
-    pub fn is_finished(&self, id: usize) -> bool {
-        self.entities
-            .get(&id)
-            .map_or(true, |entity| entity.is_finished())
+    #[test]
+    fn default_id_refers_to_nothing() {
+        let mut manager = EntityManager::new();
+        manager.insert(Dummy(1));
+        assert!(manager.get(EntityId::default()).is_none());
     }
 }
@@ -0,0 +1,184 @@
+use std::f32;
+use std::f32::consts::PI;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::entity::{Entity, EntityManager};
+use crate::render::*;
+use crate::timestep::*;
+
+const DURATION: f32 = 0.6;
+const DEBRIS_DURATION: f32 = 1.2;
+const RING_SEGMENTS: usize = 24;
+const RING_WIDTH: f32 = 4.0;
+
+struct Debris {
+    pos: Vector2,
+    prev_pos: Vector2,
+    vel: Vector2,
+    size: f32,
+    angle: f32,
+    spin: f32,
+}
+
+fn get_debris_vertices(x: f32, y: f32, size: f32, angle: f32) -> Vec<Vector2> {
+    (0..3)
+        .map(|i| {
+            let a = angle + i as f32 * PI * 2.0 / 3.0;
+            Vector2 {
+                x: x + a.cos() * size,
+                y: y + a.sin() * size,
+            }
+        })
+        .collect()
+}
+
+fn get_ring_vertices(x: f32, y: f32, radius: f32, width: f32) -> Vec<Vector2> {
+    let inner = (radius - width).max(0.0);
+    let mut vertices: Vec<Vector2> = vec![];
+    for i in 0..=RING_SEGMENTS {
+        let a = i as f32 * PI * 2.0 / RING_SEGMENTS as f32;
+        vertices.push(Vector2 {
+            x: x + a.cos() * radius,
+            y: y + a.sin() * radius,
+        });
+        vertices.push(Vector2 {
+            x: x + a.cos() * inner,
+            y: y + a.sin() * inner,
+        });
+    }
+    vertices
+}
+
+#[derive(Clone, Copy)]
+pub struct Blast {
+    pub pos: Vector2,
+    pub radius: f32,
+    // How many mines set each other off before this one, 0 when it started the chain
+    pub chain: i32,
+}
+
+impl Blast {
+    pub fn new(pos: Vector2, radius: f32) -> Self {
+        Self {
+            pos,
+            radius,
+            chain: 0,
+        }
+    }
+}
+
+/// Expanding shockwave plus flying debris. The blast itself is applied by the
+/// world once, right after the explosion was spawned.
+pub struct Explosion {
+    pos: Vector2,
+    radius: f32,
+    chain: i32,
+    age: f32,
+    debris: Vec<Debris>,
+    blast_pending: bool,
+    finished: bool,
+}
+
+impl Explosion {
+    pub fn new(blast: Blast, rng: &mut StdRng) -> Self {
+        let Blast { pos, radius, chain } = blast;
+        let debris = (0..(radius / 6.0) as usize)
+            .map(|_| {
+                let a = rng.gen_range(0.0..PI * 2.0);
+                let speed = rng.gen_range(0.5..1.5) * radius * 1.5;
+                Debris {
+                    pos,
+                    prev_pos: pos,
+                    vel: Vector2 {
+                        x: a.cos() * speed,
+                        y: a.sin() * speed,
+                    },
+                    size: rng.gen_range(1.5..4.0),
+                    angle: a,
+                    spin: rng.gen_range(-10.0..10.0),
+                }
+            })
+            .collect();
+        Self {
+            pos,
+            radius,
+            chain,
+            age: 0.0,
+            debris,
+            blast_pending: true,
+            finished: false,
+        }
+    }
+
+    /// The blast is handed out only once.
+    pub fn take_blast(&mut self) -> Option<Blast> {
+        if !self.blast_pending {
+            return None;
+        }
+        self.blast_pending = false;
+        Some(Blast {
+            pos: self.pos,
+            radius: self.radius,
+            chain: self.chain,
+        })
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.finished {
+            return;
+        }
+        self.age += dt;
+        for debris in &mut self.debris {
+            debris.prev_pos = debris.pos;
+            debris.pos.x += debris.vel.x * dt;
+            debris.pos.y += debris.vel.y * dt;
+            // water slows the pieces down quickly, then they sink
+            let drag = per_frame(0.92, dt);
+            debris.vel.x *= drag;
+            debris.vel.y = debris.vel.y * drag + 60.0 * dt;
+            debris.angle += debris.spin * dt;
+        }
+        if self.age >= DEBRIS_DURATION {
+            self.finished = true;
+        }
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let t = self.age / DURATION;
+        if t < 1.0 {
+            let radius = self.radius * (1.0 - (1.0 - t) * (1.0 - t));
+            let ring = get_ring_vertices(self.pos.x, self.pos.y, radius, RING_WIDTH);
+            d.draw_triangle_strip(&ring, Color::WHITE.alpha(1.0 - t));
+            d.draw_circle(
+                self.pos,
+                self.radius * 0.3 * (1.0 - t),
+                Color::ORANGE.alpha(1.0 - t),
+            );
+        }
+
+        let fade = 1.0 - (self.age / DEBRIS_DURATION).min(1.0);
+        for debris in &self.debris {
+            let pos = lerp(debris.prev_pos, debris.pos, alpha);
+            let vertices = get_debris_vertices(pos.x, pos.y, debris.size, debris.angle);
+            d.draw_triangle_strip(&vertices, Color::DARKGRAY.alpha(fade));
+        }
+    }
+}
+
+impl Entity for Explosion {
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn set_pos(&mut self, pos: Vector2) {
+        self.pos = pos;
+    }
+}
+
+pub type ExplosionManager = EntityManager<Explosion>;
@@ -1,18 +1,20 @@
 use std::f32;
 
-use raylib::prelude::*;
-
 use crate::{
+    collision::Collider,
     consts::WINDOW_HEIGHT,
     entity::{Entity, EntityManager},
+    render::*,
+    seabed::Seabed,
     surface_verts::{get_surface_verts_index, SurfaceVerts},
+    timestep::*,
 };
 
 use std::f32::consts::PI;
 
 const DEBUG: bool = false;
 
-fn rotate_point(x: f32, y: f32, angle: f32, origin_x: f32, origin_y: f32) -> Vector2 {
+pub fn rotate_point(x: f32, y: f32, angle: f32, origin_x: f32, origin_y: f32) -> Vector2 {
     let cos_theta = angle.cos();
     let sin_theta = angle.sin();
     let translated_x = x - origin_x;
@@ -27,6 +29,15 @@ fn rotate_point(x: f32, y: f32, angle: f32, origin_x: f32, origin_y: f32) -> Vec
     }
 }
 
+/// Shortest turn from one angle to another, wrapped to ±PI.
+pub fn angle_diff(from: f32, to: f32) -> f32 {
+    let mut diff = to - from;
+    if diff.abs() > PI {
+        diff = (diff.abs() - 2.0 * PI) * diff.signum();
+    }
+    diff
+}
+
 const COLORS: [Color; 4] = [
     Color::LIGHTBLUE,
     Color::LIGHTSALMON,
@@ -34,15 +45,16 @@ const COLORS: [Color; 4] = [
     Color::LIGHTYELLOW,
 ];
 
-fn draw_fish(
-    mut d: RaylibDrawHandle,
+/// Kinds of fish, one colour each.
+pub const FISH_TYPES: usize = COLORS.len();
+
+pub fn get_fish_vertices(
     wobble: f32,
     fish_x: f32,
     fish_y: f32,
     rotation: f32,
     scale: f32,
-    fish_type: i32,
-) -> RaylibDrawHandle {
+) -> Vec<Vector2> {
     let pos_wobble = ((wobble * 1.0).sin() + 1.0) * 0.5;
     let tail_wobble = ((wobble * 30.0 + fish_x + fish_y).sin() + 1.0) * 0.5;
 
@@ -92,16 +104,27 @@ fn draw_fish(
         },
     ];
 
-    let rotated_vertices: Vec<Vector2> = vertices
+    vertices
         .iter()
         .map(|v| rotate_point(v.x, v.y, rotation, x, y))
-        .collect();
+        .collect()
+}
+
+fn draw_fish(
+    d: &mut dyn Renderer,
+    wobble: f32,
+    fish_x: f32,
+    fish_y: f32,
+    rotation: f32,
+    scale: f32,
+    fish_type: i32,
+) {
+    let rotated_vertices = get_fish_vertices(wobble, fish_x, fish_y, rotation, scale);
 
     d.draw_triangle_strip(
         &rotated_vertices,
         COLORS[(fish_type % (COLORS.len() as i32)) as usize],
     );
-    d
 }
 
 pub struct Fish {
@@ -112,6 +135,7 @@ pub struct Fish {
     direction: f32,
     pub target_reached: bool,
     draw_pos_y: f32,
+    prev_draw_pos: Vector2,
     scale: f32,
     fish_type: usize,
     fish_index: usize,
@@ -129,20 +153,25 @@ impl Fish {
             direction: 0.0,
             target_reached: true,
             draw_pos_y: 0.0,
+            prev_draw_pos: Vector2 { x: pos.x, y: 0.0 },
             scale,
             fish_type,
             fish_index: 0,
         }
     }
 
-    pub fn update(&mut self, dt: f32, surface_verts: &SurfaceVerts, index: usize) {
+    pub fn update(&mut self, dt: f32, surface_verts: &SurfaceVerts, seabed: &Seabed, index: usize) {
         if self.finished {
             return;
         }
 
         self.fish_index = index;
+        self.prev_draw_pos = Vector2 {
+            x: self.pos.x,
+            y: self.draw_pos_y,
+        };
 
-        let fish_index = get_surface_verts_index(&surface_verts, self.pos.x);
+        let fish_index = get_surface_verts_index(surface_verts, self.pos.x);
         let surface_y = surface_verts.layer_a[fish_index].y + 30.0;
 
         let mut dir_change_fact = 0.005;
@@ -157,12 +186,9 @@ impl Fish {
 
         let direction = f32::atan2(dy, dx);
 
-        let mut diff = direction - self.direction;
-        if diff.abs() > PI {
-            diff = (diff.abs() - 2.0 * PI) * diff.signum();
-        }
+        let diff = angle_diff(self.direction, direction);
 
-        self.direction += diff * dir_change_fact;
+        self.direction += diff * (1.0 - per_frame(1.0 - dir_change_fact, dt));
 
         let max_dy = MAX_SPEED * self.direction.sin();
         let max_dx = MAX_SPEED * self.direction.cos();
@@ -182,7 +208,17 @@ impl Fish {
             .max(0.0)
             .min(1.0);
         let y_surface_effect = surface_y + effect * (self.pos.y - surface_y);
-        self.draw_pos_y = self.draw_pos_y * 0.95 + y_surface_effect * 0.05;
+        let smoothing = per_frame(0.95, dt);
+        self.draw_pos_y = self.draw_pos_y * smoothing + y_surface_effect * (1.0 - smoothing);
+
+        // Fish swim around rocks, a fish that bumps into one picks a new target
+        let clearance = 2.0 * self.scale;
+        let draw_pos_y = seabed.push_out(self.pos.x, self.draw_pos_y, clearance);
+        if draw_pos_y != self.draw_pos_y {
+            self.pos.y += draw_pos_y - self.draw_pos_y;
+            self.draw_pos_y = draw_pos_y;
+            self.target_reached = true;
+        }
 
         self.pos.x += dx;
         if self.pos.x <= -90.0 {
@@ -204,33 +240,39 @@ impl Fish {
         self.wobble += dt;
     }
 
-    pub fn draw<'d>(&self, mut d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let draw_pos = lerp(
+            self.prev_draw_pos,
+            Vector2 {
+                x: self.pos.x,
+                y: self.draw_pos_y,
+            },
+            alpha,
+        );
         if DEBUG {
-            d.draw_circle(
-                self.target_pos.x as i32,
-                (self.target_pos.y + self.draw_pos_y - self.pos.y) as i32,
-                3.0,
-                Color::DARKGREEN,
-            );
+            let target = Vector2 {
+                x: self.target_pos.x,
+                y: self.target_pos.y + self.draw_pos_y - self.pos.y,
+            };
+            d.draw_circle(target, 3.0, Color::DARKGREEN);
 
-            let x = self.target_pos.x as i32;
-            let y = (self.target_pos.y + self.draw_pos_y - self.pos.y) as i32;
             let n: String = self.fish_index.to_string();
-            d.draw_text(&n, x, y, 10, Color::WHITE);
+            d.draw_text(&n, target.x as i32, target.y as i32, 10, Color::WHITE);
 
             d.draw_line(
-                self.pos.x as i32,
-                self.draw_pos_y as i32,
-                self.target_pos.x as i32,
-                (self.target_pos.y + self.draw_pos_y - self.pos.y) as i32,
+                Vector2 {
+                    x: self.pos.x,
+                    y: self.draw_pos_y,
+                },
+                target,
                 Color::LIGHTGREEN,
             );
         }
         draw_fish(
             d,
             self.wobble,
-            self.pos.x,
-            self.draw_pos_y,
+            draw_pos.x,
+            draw_pos.y,
             self.direction,
             self.scale,
             self.fish_type as i32,
@@ -242,14 +284,29 @@ impl Fish {
         self.target_reached = false;
     }
 
+    /// Sends the fish fleeing if it is within `radius` of `center`.
+    pub fn scatter_from(&mut self, center: Vector2, radius: f32) {
+        let dx = self.pos.x - center.x;
+        let dy = self.draw_pos_y - center.y;
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist >= radius || dist == 0.0 {
+            return;
+        }
+        let flee = radius * 1.5;
+        self.set_target_pos(Vector2 {
+            x: self.pos.x + dx / dist * flee,
+            y: self.pos.y + dy / dist * flee,
+        });
+    }
+
     pub fn has_reached_target(&self) -> bool {
         self.target_reached
     }
 }
 
 impl Entity for Fish {
-    fn draw<'d>(&self, d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        self.draw(d)
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
     }
 
     fn is_finished(&self) -> bool {
@@ -261,4 +318,16 @@ impl Entity for Fish {
     }
 }
 
+impl Collider for Fish {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        get_fish_vertices(
+            self.wobble,
+            self.pos.x,
+            self.draw_pos_y,
+            self.direction,
+            self.scale,
+        )
+    }
+}
+
 pub type FishManager = EntityManager<Fish>;
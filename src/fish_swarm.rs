@@ -1,13 +1,13 @@
-use lazy_static::lazy_static;
 use std::f32;
-use std::sync::atomic::{AtomicUsize, Ordering};
 
+use rand::rngs::StdRng;
 use rand::Rng;
-use raylib::prelude::*;
 
 use crate::consts::*;
-use crate::entity::{Entity, EntityManager};
-use crate::fish::{Fish, FishManager};
+use crate::entity::{Entity, EntityId, EntityManager};
+use crate::fish::{Fish, FishManager, FISH_TYPES};
+use crate::render::*;
+use crate::seabed::Seabed;
 use crate::surface_verts::SurfaceVerts;
 
 pub struct FishSwarm {
@@ -16,12 +16,14 @@ pub struct FishSwarm {
     relaxed: i32,
 }
 
-lazy_static! {
-    static ref INSTANCE_COUNT: AtomicUsize = AtomicUsize::new(0);
-}
-
-fn make_new_target_pos(pos: Vector2, rx0: f32, rx1: f32, ry0: f32, ry1: f32) -> Vector2 {
-    let mut rng = rand::thread_rng();
+fn make_new_target_pos(
+    rng: &mut StdRng,
+    pos: Vector2,
+    rx0: f32,
+    rx1: f32,
+    ry0: f32,
+    ry1: f32,
+) -> Vector2 {
     Vector2 {
         x: (pos.x + rng.gen_range(rx0..rx1))
             .max(-100.0)
@@ -33,12 +35,10 @@ fn make_new_target_pos(pos: Vector2, rx0: f32, rx1: f32, ry0: f32, ry1: f32) ->
 }
 
 impl FishSwarm {
-    pub fn new(count: i32, relaxed: i32) -> Self {
-        INSTANCE_COUNT.fetch_add(1, Ordering::SeqCst);
-        let mut rng = rand::thread_rng();
+    pub fn new(count: i32, relaxed: i32, rng: &mut StdRng) -> Self {
         let mut fish_manager = FishManager::new();
         let fish_scale = rng.gen_range(1.0..3.0);
-        let fish_type = INSTANCE_COUNT.load(Ordering::SeqCst);
+        let fish_type = rng.gen_range(0..FISH_TYPES);
         for _ in 0..count {
             fish_manager.insert(Fish::new(Vector2::zero(), fish_scale, fish_type));
         }
@@ -49,30 +49,37 @@ impl FishSwarm {
         }
     }
 
-    pub fn update(&mut self, dt: f32, surface_verts: &SurfaceVerts) -> bool {
+    pub fn update(
+        &mut self,
+        dt: f32,
+        surface_verts: &SurfaceVerts,
+        seabed: &Seabed,
+        rng: &mut StdRng,
+    ) -> bool {
         if self.finished {
             return true;
         }
 
         let mut poss: Vec<Vector2> = vec![];
 
-        self.finished = self.fish_manager.update(|fish, i| {
+        self.finished = self.fish_manager.update(|fish, i, _| {
             if fish.pos.y == 0.0 {
-                let mut rng = rand::thread_rng();
                 fish.pos = Vector2 {
                     x: WINDOW_WIDTH as f32 + 20.0 + (i as f32) * 10.0,
                     y: rng.gen_range(100.0..WINDOW_HEIGHT as f32),
                 }
             }
-            fish.update(dt, surface_verts, i as usize);
+            fish.update(dt, surface_verts, seabed, i);
             if fish.has_reached_target() {
                 if i == 0 {
-                    fish.set_target_pos(make_new_target_pos(fish.pos, -40.0, -1.0, -40.0, 45.0))
+                    fish.set_target_pos(make_new_target_pos(
+                        rng, fish.pos, -40.0, -1.0, -40.0, 45.0,
+                    ))
                 } else {
-                    let p = ((i - 1) / 2) as usize;
+                    let p = (i - 1) / 2;
                     let radius = 10.0 + (self.relaxed as f32) * 10.0;
                     fish.set_target_pos(make_new_target_pos(
-                        poss[p], -radius, radius, -radius, radius,
+                        rng, poss[p], -radius, radius, -radius, radius,
                     ))
                 }
             }
@@ -82,20 +89,35 @@ impl FishSwarm {
         self.finished
     }
 
-    pub fn draw<'d>(&self, mut d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        self.fish_manager.draw(d)
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.fish_manager.draw(d, alpha)
+    }
+
+    pub fn iter_fish(&self) -> impl Iterator<Item = (EntityId, &Fish)> {
+        self.fish_manager.iter()
+    }
+
+    pub fn scatter_from(&mut self, center: Vector2, radius: f32) {
+        for (_, fish) in self.fish_manager.iter_mut() {
+            fish.scatter_from(center, radius);
+        }
+    }
+
+    pub fn remove_fish(&mut self, id: EntityId) -> Option<Fish> {
+        self.fish_manager.remove(id)
     }
 
-    pub fn in_last_sector(&mut self) -> bool {
+    // The first fish is the leader the others follow
+    pub fn in_last_sector(&self) -> bool {
         self.fish_manager
             .head()
-            .map_or(false, |fish| fish.pos.x >= (WINDOW_WIDTH as f32) * 0.75)
+            .is_some_and(|fish| fish.pos.x >= (WINDOW_WIDTH as f32) * 0.75)
     }
 }
 
 impl Entity for FishSwarm {
-    fn draw<'d>(&self, d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        self.draw(d)
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
     }
 
     fn is_finished(&self) -> bool {
@@ -0,0 +1,438 @@
+use std::io;
+
+use raylib::ffi::KeyboardKey::*;
+use raylib::prelude::RaylibHandle;
+
+use crate::consts::*;
+use crate::progress::*;
+use crate::render::*;
+use crate::replay::*;
+use crate::rng::*;
+use crate::timestep::*;
+use crate::upgrade::*;
+use crate::world::*;
+
+/// Keyboard state sampled once per frame, so the world never touches raylib input.
+#[derive(Clone, Copy, Default)]
+pub struct Input {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    pub switch_weapon: bool,
+    pub confirm: bool,
+    pub pause: bool,
+}
+
+impl Input {
+    pub fn read(rl: &RaylibHandle) -> Self {
+        Self {
+            up: rl.is_key_down(KEY_UP),
+            down: rl.is_key_down(KEY_DOWN),
+            left: rl.is_key_down(KEY_LEFT),
+            right: rl.is_key_down(KEY_RIGHT),
+            fire: rl.is_key_down(KEY_SPACE),
+            switch_weapon: rl.is_key_pressed(KEY_TAB),
+            confirm: rl.is_key_pressed(KEY_ENTER),
+            pause: rl.is_key_pressed(KEY_P),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameState {
+    Title,
+    // Spending banked gold on upgrades before the next dive
+    Shop,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+pub struct Game {
+    state: GameState,
+    world: World,
+    timestep: FixedTimestep,
+    // Set from the command line, every run replays the same level
+    fixed_seed: Option<u64>,
+    recording: InputLog,
+    record_path: Option<String>,
+    playback: Option<Playback>,
+    progress: Progress,
+    progress_path: Option<String>,
+    shop_cursor: usize,
+    // Held keys of the last update, the shop reacts to presses only
+    last_input: Input,
+    // A key press has to reach exactly one tick, even on frames without any
+    switch_pending: bool,
+}
+
+impl Game {
+    pub fn new(
+        fixed_seed: Option<u64>,
+        record_path: Option<String>,
+        progress_path: Option<String>,
+    ) -> Self {
+        let seed = fixed_seed.unwrap_or_else(random_seed);
+        let mut progress_path = progress_path;
+        let progress = match &progress_path {
+            Some(path) => match Progress::load(path) {
+                Ok(progress) => progress,
+                // No file yet is just a first start
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Progress::default(),
+                // A file that does not load is left alone instead of overwritten
+                Err(err) => {
+                    eprintln!(
+                        "Could not load progress {}: {}, progress will not be saved",
+                        path, err
+                    );
+                    progress_path = None;
+                    Progress::default()
+                }
+            },
+            None => Progress::default(),
+        };
+        Self {
+            state: GameState::Title,
+            world: World::new(seed, &progress.upgrades),
+            timestep: FixedTimestep::new(),
+            fixed_seed,
+            recording: InputLog::new(seed, progress.upgrades),
+            record_path,
+            playback: None,
+            progress,
+            progress_path,
+            shop_cursor: 0,
+            last_input: Input::default(),
+            switch_pending: false,
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.save_recording();
+        self.bank_gold();
+        let seed = self.fixed_seed.unwrap_or_else(random_seed);
+        let upgrades = self.progress.upgrades;
+        self.world = World::new(seed, &upgrades);
+        self.timestep = FixedTimestep::new();
+        self.recording = InputLog::new(seed, upgrades);
+        self.playback = None;
+        self.state = GameState::Playing;
+    }
+
+    /// Starts a run that is driven by the recorded input instead of the keyboard.
+    pub fn play_replay(&mut self, log: InputLog) {
+        self.save_recording();
+        self.bank_gold();
+        // The ship is built with the upgrades of the recorded run, not the current ones
+        let playback = Playback::new(log);
+        self.world = World::new(playback.seed(), &playback.upgrades());
+        self.timestep = FixedTimestep::new();
+        self.recording = InputLog::new(playback.seed(), playback.upgrades());
+        self.playback = Some(playback);
+        self.state = GameState::Playing;
+    }
+
+    /// Writes the input of the current run, if any, to the record path.
+    pub fn save_recording(&mut self) {
+        let Some(path) = &self.record_path else {
+            return;
+        };
+        if self.recording.is_empty() {
+            return;
+        }
+        if let Err(err) = self.recording.save(path) {
+            eprintln!("Could not save replay to {}: {}", path, err);
+        }
+        self.recording = InputLog::new(self.recording.seed(), self.recording.upgrades());
+    }
+
+    /// Adds the gold of the current run to the bank, replays don't pay out.
+    pub fn bank_gold(&mut self) {
+        let gold = self.world.take_gold();
+        if gold <= 0 || self.playback.is_some() {
+            return;
+        }
+        self.progress.gold += gold as u32;
+        self.save_progress();
+    }
+
+    fn save_progress(&self) {
+        let Some(path) = &self.progress_path else {
+            return;
+        };
+        if let Err(err) = self.progress.save(path) {
+            eprintln!("Could not save progress to {}: {}", path, err);
+        }
+    }
+
+    fn update_shop(&mut self, input: &Input) {
+        let last = self.last_input;
+        if input.up && !last.up {
+            self.shop_cursor = (self.shop_cursor + UPGRADE_KINDS.len() - 1) % UPGRADE_KINDS.len();
+        }
+        if input.down && !last.down {
+            self.shop_cursor = (self.shop_cursor + 1) % UPGRADE_KINDS.len();
+        }
+        if input.fire && !last.fire && self.progress.buy(UPGRADE_KINDS[self.shop_cursor]) {
+            self.save_progress();
+        }
+        if input.confirm {
+            self.restart();
+        }
+    }
+
+    fn tick(&mut self, input: &Input) {
+        let tick_input = match &mut self.playback {
+            Some(playback) => match playback.next() {
+                Some(tick_input) => tick_input,
+                None => {
+                    self.state = GameState::GameOver;
+                    return;
+                }
+            },
+            None => {
+                self.recording.push(input);
+                *input
+            }
+        };
+        self.world.update(TICK_DT, &tick_input);
+        if self.world.is_game_over() {
+            self.state = GameState::GameOver;
+            self.save_recording();
+            self.bank_gold();
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, input: &Input) {
+        self.update_state(dt, input);
+        self.last_input = *input;
+    }
+
+    fn update_state(&mut self, dt: f32, input: &Input) {
+        match self.state {
+            GameState::Title => {
+                if input.confirm {
+                    self.state = GameState::Shop;
+                }
+            }
+            GameState::Shop => self.update_shop(input),
+            GameState::Playing => {
+                if input.pause {
+                    self.state = GameState::Paused;
+                    return;
+                }
+                self.switch_pending |= input.switch_weapon;
+                for _ in 0..self.timestep.advance(dt) {
+                    let tick_input = Input {
+                        switch_weapon: self.switch_pending,
+                        ..*input
+                    };
+                    self.switch_pending = false;
+                    self.tick(&tick_input);
+                    if self.state != GameState::Playing {
+                        break;
+                    }
+                }
+            }
+            GameState::Paused => {
+                if input.pause || input.confirm {
+                    self.state = GameState::Playing;
+                }
+            }
+            GameState::GameOver => {
+                if input.confirm {
+                    self.state = GameState::Shop;
+                }
+            }
+        }
+    }
+
+    pub fn draw(&mut self, d: &mut dyn Renderer) {
+        self.world.draw(d, self.timestep.alpha());
+        match self.state {
+            GameState::Title => {
+                draw_banner(d, "DEEP SEA SCRAMBLE", "Press ENTER to start");
+                draw_bank(d, self.progress.gold);
+            }
+            GameState::Shop => draw_shop(d, &self.progress, self.shop_cursor),
+            GameState::Playing => {
+                if self.playback.is_some() {
+                    let width = d.measure_text("REPLAY", 20);
+                    d.draw_text("REPLAY", (WINDOW_WIDTH - width) / 2, 10, 20, Color::RED);
+                }
+            }
+            GameState::Paused => draw_banner(d, "PAUSED", "Press P to continue"),
+            GameState::GameOver => {
+                draw_banner(d, "GAME OVER", "Press ENTER for the shop");
+                draw_bank(d, self.progress.gold);
+            }
+        }
+    }
+}
+
+fn draw_banner(d: &mut dyn Renderer, title: &str, subtitle: &str) {
+    let title_width = d.measure_text(title, 40);
+    let subtitle_width = d.measure_text(subtitle, 20);
+    d.draw_text(
+        title,
+        (WINDOW_WIDTH - title_width) / 2,
+        WINDOW_HEIGHT / 2 - 40,
+        40,
+        Color::WHITE,
+    );
+    d.draw_text(
+        subtitle,
+        (WINDOW_WIDTH - subtitle_width) / 2,
+        WINDOW_HEIGHT / 2 + 10,
+        20,
+        Color::WHITE,
+    );
+}
+
+fn draw_bank(d: &mut dyn Renderer, gold: u32) {
+    let text = format!("{} GOLD IN THE BANK", gold);
+    let width = d.measure_text(&text, 10);
+    d.draw_text(
+        &text,
+        (WINDOW_WIDTH - width) / 2,
+        WINDOW_HEIGHT / 2 + 40,
+        10,
+        Color::GOLD,
+    );
+}
+
+fn draw_shop(d: &mut dyn Renderer, progress: &Progress, cursor: usize) {
+    let title_width = d.measure_text("SHOP", 40);
+    d.draw_text(
+        "SHOP",
+        (WINDOW_WIDTH - title_width) / 2,
+        WINDOW_HEIGHT / 2 - 120,
+        40,
+        Color::WHITE,
+    );
+    let left = WINDOW_WIDTH / 2 - 150;
+    for (i, kind) in UPGRADE_KINDS.into_iter().enumerate() {
+        let y = WINDOW_HEIGHT / 2 - 60 + i as i32 * 20;
+        let level = progress.upgrades.level(kind);
+        let price = match progress.upgrades.next_cost(kind) {
+            Some(cost) => format!("{} GOLD", cost),
+            None => "MAX".to_string(),
+        };
+        let color = if i == cursor {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+        let marker = if i == cursor { ">" } else { " " };
+        d.draw_text(&format!("{} {}", marker, kind.name()), left, y, 10, color);
+        d.draw_text(
+            &format!("LV {}/{}", level, MAX_LEVEL),
+            left + 170,
+            y,
+            10,
+            color,
+        );
+        d.draw_text(&price, left + 230, y, 10, color);
+    }
+    let hint = "UP/DOWN to choose, SPACE to buy, ENTER to dive";
+    let hint_width = d.measure_text(hint, 10);
+    d.draw_text(
+        hint,
+        (WINDOW_WIDTH - hint_width) / 2,
+        WINDOW_HEIGHT / 2 + 60,
+        10,
+        Color::WHITE,
+    );
+    draw_bank(d, progress.gold);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keys that change every now and then, so the ship moves, fires and switches weapons
+    fn scripted_input(tick: usize) -> Input {
+        Input {
+            up: tick % 240 < 60,
+            down: (120..170).contains(&(tick % 240)),
+            right: tick % 300 < 100,
+            fire: tick % 20 < 5,
+            switch_weapon: tick % 400 == 399,
+            ..Default::default()
+        }
+    }
+
+    // Leaves the title screen and the shop
+    fn start(game: &mut Game) {
+        let confirm = Input {
+            confirm: true,
+            ..Default::default()
+        };
+        game.update(0.0, &confirm);
+        game.update(0.0, &confirm);
+    }
+
+    // Plays `ticks` ticks of scripted input and returns the digest of the last frame
+    fn play(game: &mut Game, ticks: usize) -> u64 {
+        let mut renderer = RecordingRenderer::new();
+        for tick in 0..ticks {
+            game.update(TICK_DT, &scripted_input(tick));
+            renderer.clear();
+            game.draw(&mut renderer);
+            assert!(!renderer.commands.is_empty());
+        }
+        renderer.digest()
+    }
+
+    // The world alone, the banners differ between a run and its replay
+    fn world_digest(game: &mut Game) -> u64 {
+        let mut renderer = RecordingRenderer::new();
+        game.world.draw(&mut renderer, game.timestep.alpha());
+        renderer.digest()
+    }
+
+    fn run(seed: u64, ticks: usize) -> u64 {
+        let mut game = Game::new(Some(seed), None, None);
+        start(&mut game);
+        play(&mut game, ticks)
+    }
+
+    #[test]
+    fn headless_run_draws_every_frame() {
+        let mut game = Game::new(None, None, None);
+        start(&mut game);
+        play(&mut game, 600);
+    }
+
+    #[test]
+    fn same_seed_draws_the_same_frames() {
+        assert_eq!(run(42, 600), run(42, 600));
+    }
+
+    #[test]
+    fn different_seeds_draw_different_frames() {
+        assert_ne!(run(1, 600), run(2, 600));
+    }
+
+    #[test]
+    fn replay_draws_the_recorded_run() {
+        let path = std::env::temp_dir().join("dss_game_replay.dssr");
+        let path = path.to_str().unwrap();
+        let mut game = Game::new(Some(7), Some(path.to_string()), None);
+        start(&mut game);
+        play(&mut game, 300);
+        let recorded = world_digest(&mut game);
+        game.save_recording();
+
+        let log = InputLog::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let mut replay = Game::new(None, None, None);
+        replay.play_replay(log);
+        for _ in 0..300 {
+            replay.update(TICK_DT, &Input::default());
+        }
+        assert_eq!(world_digest(&mut replay), recorded);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::consts::*;
+use crate::power_up::*;
+use crate::render::*;
+use crate::score::*;
+use crate::ship::*;
+use crate::weapon::*;
+
+const HULL_BAR_WIDTH: f32 = 100.0;
+const BOSS_BAR_WIDTH: f32 = 240.0;
+const EFFECT_ICON_SIZE: f32 = 16.0;
+// Part of a full tank below which the air bar warns
+const LOW_AIR: f32 = 0.25;
+
+pub fn draw_hud(
+    d: &mut dyn Renderer,
+    score: &Score,
+    weapons: &Weapons,
+    effects: &Effects,
+    air: f32,
+    seed: u64,
+) {
+    d.draw_text(
+        &format!("SCORE {}", score.points()),
+        10,
+        10,
+        20,
+        Color::WHITE,
+    );
+    d.draw_text(
+        &format!("{} m", score.distance as i32),
+        10,
+        32,
+        10,
+        Color::WHITE,
+    );
+    d.draw_text(&format!("GOLD {}", score.gold), 10, 44, 10, Color::GOLD);
+    if score.best_chain > 0 {
+        d.draw_text(
+            &format!("BEST CHAIN x{}", score.best_chain),
+            10,
+            56,
+            10,
+            Color::GOLD,
+        );
+    }
+    d.draw_text(
+        &format!("SEED {}", seed),
+        10,
+        WINDOW_HEIGHT - 20,
+        10,
+        Color::WHITE,
+    );
+
+    // Hull bar
+    let x = WINDOW_WIDTH as f32 - HULL_BAR_WIDTH - 10.0;
+    let y = 10.0;
+    let fill = HULL_BAR_WIDTH * (score.hull / MAX_HULL).max(0.0);
+    d.draw_triangle_strip(&bar_vertices(x, y, HULL_BAR_WIDTH, 8.0), Color::DARKGRAY);
+    d.draw_triangle_strip(&bar_vertices(x, y, fill, 8.0), Color::LIGHTGREEN);
+
+    // Air bar right below, turns red when it is time to go up
+    let air_color = if air < LOW_AIR {
+        Color::RED
+    } else {
+        Color::SKYBLUE
+    };
+    d.draw_triangle_strip(
+        &bar_vertices(x, y + 11.0, HULL_BAR_WIDTH, 3.0),
+        Color::DARKGRAY,
+    );
+    d.draw_triangle_strip(
+        &bar_vertices(x, y + 11.0, HULL_BAR_WIDTH * air, 3.0),
+        air_color,
+    );
+
+    // Remaining lives as small ships
+    for i in 0..score.lives {
+        let life_x = x + i as f32 * 35.0;
+        let life_y = y + 25.0;
+        let vertices: Vec<Vector2> = get_ship_vertices(0.0, 0.0)
+            .iter()
+            .map(|v| Vector2 {
+                x: life_x + v.x * 0.6,
+                y: life_y + v.y * 0.6,
+            })
+            .collect();
+        d.draw_triangle_strip(&vertices, Color::WHITESMOKE);
+    }
+
+    // Selected weapon with its ammo and the next round reloading
+    let weapon = weapons.selected();
+    let weapon_y = y + 40.0;
+    d.draw_text(
+        &format!("{} {}/{}", weapon.kind.name(), weapon.ammo, weapon.capacity),
+        x as i32,
+        weapon_y as i32,
+        10,
+        Color::WHITE,
+    );
+    let reload = HULL_BAR_WIDTH * weapon.reload_progress();
+    d.draw_triangle_strip(
+        &bar_vertices(x, weapon_y + 12.0, HULL_BAR_WIDTH, 3.0),
+        Color::DARKGRAY,
+    );
+    d.draw_triangle_strip(
+        &bar_vertices(x, weapon_y + 12.0, reload, 3.0),
+        Color::YELLOW,
+    );
+
+    // Running power-ups, each with the time it has left
+    let effect_y = weapon_y + 22.0;
+    for (i, effect) in effects.iter().enumerate() {
+        let icon_x = x + i as f32 * (EFFECT_ICON_SIZE + 4.0);
+        let color = effect.kind.color();
+        d.draw_triangle_strip(
+            &bar_vertices(icon_x, effect_y, EFFECT_ICON_SIZE, EFFECT_ICON_SIZE),
+            color,
+        );
+        let width = d.measure_text(effect.kind.letter(), 10);
+        d.draw_text(
+            effect.kind.letter(),
+            (icon_x + EFFECT_ICON_SIZE * 0.5) as i32 - width / 2,
+            effect_y as i32 + 3,
+            10,
+            Color::BLACK,
+        );
+        d.draw_triangle_strip(
+            &bar_vertices(
+                icon_x,
+                effect_y + EFFECT_ICON_SIZE + 2.0,
+                EFFECT_ICON_SIZE * effect.remaining(),
+                2.0,
+            ),
+            color,
+        );
+    }
+}
+
+/// Name and health bar of the boss along the top of the window.
+pub fn draw_boss_bar(d: &mut dyn Renderer, name: &str, health: f32) {
+    let x = (WINDOW_WIDTH as f32 - BOSS_BAR_WIDTH) * 0.5;
+    let y = 14.0;
+    let width = d.measure_text(name, 10);
+    d.draw_text(
+        name,
+        (WINDOW_WIDTH - width) / 2,
+        y as i32 - 12,
+        10,
+        Color::WHITE,
+    );
+    d.draw_triangle_strip(&bar_vertices(x, y, BOSS_BAR_WIDTH, 6.0), Color::DARKGRAY);
+    d.draw_triangle_strip(
+        &bar_vertices(x, y, BOSS_BAR_WIDTH * health.max(0.0), 6.0),
+        Color::RED,
+    );
+}
+
+fn bar_vertices(x: f32, y: f32, width: f32, height: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 { x, y },
+        Vector2 { x, y: y + height },
+        Vector2 { x: x + width, y },
+        Vector2 {
+            x: x + width,
+            y: y + height,
+        },
+    ]
+}
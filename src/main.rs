@@ -1,98 +1,118 @@
-use rand::Rng;
-use raylib::ffi::KeyboardKey::*;
-use raylib::prelude::*;
-
+mod boss;
 mod bubbles;
 mod bullet;
+mod collision;
+mod commands;
 mod consts;
+mod enemy_sub;
 mod entity;
+mod explosion;
 mod fish;
 mod fish_swarm;
+mod game;
+mod hud;
 mod mine;
+mod power_up;
+mod progress;
+mod render;
+mod replay;
+mod rng;
+mod score;
+mod seabed;
 mod ship;
+mod splash;
 mod surface_verts;
+mod timestep;
+mod treasure;
+mod upgrade;
+mod warship;
 mod water;
+mod weapon;
+mod world;
 
-use bubbles::*;
-use bullet::*;
 use consts::*;
-use fish_swarm::*;
-use mine::*;
-use ship::*;
-use water::*;
+use game::*;
+use render::*;
+use replay::*;
+use timestep::*;
 
-fn main() {
-    let (mut rl, thread) = raylib::init()
-        .size(WINDOW_WIDTH, WINDOW_HEIGHT)
-        .title("Deep Sea Scramble!")
-        .build();
+const DEFAULT_RECORD_PATH: &str = "last_run.dssr";
+const DEFAULT_PROGRESS_PATH: &str = "progress.dssp";
 
-    let mut arena_x = 0.0;
+// Runs the game loop without a window, e.g. `--headless 600` for ten seconds of play
+fn run_headless(mut game: Game, frames: usize) {
+    let mut renderer = RecordingRenderer::new();
+    // Leaves the title screen and the shop, a replay is already running
+    for _ in 0..2 {
+        game.update(
+            0.0,
+            &Input {
+                confirm: true,
+                ..Default::default()
+            },
+        );
+    }
+    for _ in 0..frames {
+        game.update(TICK_DT, &Input::default());
+        renderer.clear();
+        game.draw(&mut renderer);
+    }
+    game.save_recording();
+    game.bank_gold();
+    println!(
+        "{} frames, {} draw commands in last frame, digest {:016x}",
+        frames,
+        renderer.commands.len(),
+        renderer.digest()
+    );
+}
 
-    let mut water = Water::new();
-    let mut bubbles_manager = BubblesManager::new();
-    let mut bullet_manager = BulletManager::new();
-    let mut fish_swarm_manager = FishSwarmManager::new();
-    let mut mine_manager = MineManager::new();
-    let mut ship = Ship::new();
+fn arg_value<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    let index = args.iter().position(|arg| arg == name)?;
+    args.get(index + 1).and_then(|arg| arg.parse().ok())
+}
 
-    while !rl.window_should_close() {
-        let dt = rl.get_frame_time();
-        arena_x -= dt * 100.0;
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let seed: Option<u64> = arg_value(&args, "--seed");
+    let headless = args.iter().any(|arg| arg == "--headless");
+    // Windowed runs always keep the last run, headless ones only on request
+    let record_path: Option<String> = arg_value(&args, "--record")
+        .or_else(|| (!headless).then(|| DEFAULT_RECORD_PATH.to_string()));
+    // Same for the gold the player has banked
+    let progress_path: Option<String> = arg_value(&args, "--progress")
+        .or_else(|| (!headless).then(|| DEFAULT_PROGRESS_PATH.to_string()));
 
-        mine_manager.update(|entity, _| {
-            entity.update(
-                dt,
-                arena_x,
-                &mut bubbles_manager,
-                &ship,
-                &water.surface_verts,
-            );
-        });
-        if let Some((step, surface_pos)) = water.update(arena_x) {
-            if step == 0 {
-                mine_manager.insert(Mine::new(surface_pos, &ship));
+    let mut game = Game::new(seed, record_path, progress_path);
+    if let Some(replay_path) = arg_value::<String>(&args, "--replay") {
+        match InputLog::load(&replay_path) {
+            Ok(log) => game.play_replay(log),
+            Err(err) => {
+                eprintln!("Could not load replay {}: {}", replay_path, err);
+                std::process::exit(1);
             }
         }
+    }
 
-        let mut has_lead_fish_in_last_sector = false;
-        fish_swarm_manager.update(|fish_swarm, _| {
-            if fish_swarm.update(dt, &water.surface_verts) {
-                // no fish in swarm
-            }
-            if fish_swarm.in_last_sector() {
-                has_lead_fish_in_last_sector = true;
-            }
-        });
-
-        if !has_lead_fish_in_last_sector {
-            let mut rng = rand::thread_rng();
-            fish_swarm_manager.insert(FishSwarm::new(rng.gen_range(10..30), rng.gen_range(0..9)));
-        }
+    if headless {
+        run_headless(game, arg_value(&args, "--headless").unwrap_or(600));
+        return;
+    }
 
-        bubbles_manager.update(|bubbles, _| bubbles.update(dt, &water.surface_verts));
-        bullet_manager.update(|bullet, _| bullet.update(dt));
-        ship.update(&mut bubbles_manager, &water.surface_verts);
+    let (mut rl, thread) = raylib::init()
+        .size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .title("Deep Sea Scramble!")
+        .build();
 
-        // Keyboard
-        if rl.is_key_down(KEY_UP) {
-            ship.pos.y -= 1.0;
-        }
-        if rl.is_key_down(KEY_DOWN) {
-            ship.pos.y += 1.0;
-        }
-        if rl.is_key_down(KEY_SPACE) {
-            ship.start_bullet(&mut bubbles_manager, &mut bullet_manager);
-        }
+    while !rl.window_should_close() {
+        let dt = rl.get_frame_time();
+        let input = Input::read(&rl);
+        game.update(dt, &input);
 
         // Draw
         let mut d = rl.begin_drawing(&thread);
-        d.clear_background(Color::LIGHTSKYBLUE);
-        let d = water.draw(d);
-        let d = fish_swarm_manager.draw(d);
-        let d = bullet_manager.draw(d);
-        let d = bubbles_manager.draw(d);
-        let d = mine_manager.draw(d);
-        ship.draw(d);
+        game.draw(&mut d);
     }
+    game.save_recording();
+    game.bank_gold();
 }
@@ -1,15 +1,20 @@
 use std::f32;
 
-use raylib::prelude::*;
+use rand::rngs::StdRng;
+use rand::Rng;
 
 use crate::bubbles::*;
-use crate::consts::*;
-use crate::entity::Entity;
+use crate::collision::Collider;
+use crate::commands::*;
 use crate::entity::EntityManager;
+use crate::entity::{Entity, EntityId};
+use crate::explosion::Blast;
+use crate::render::*;
+use crate::seabed::*;
 use crate::ship::*;
-use crate::surface_verts::*;
+use crate::timestep::*;
 
-fn get_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
+pub fn get_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
     vec![
         Vector2 { x: x, y: y + 2.0 }, // Bottom center of the mine base
         Vector2 {
@@ -32,83 +37,348 @@ fn get_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
     ]
 }
 
+pub fn get_proximity_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 { x: x - 10.0, y }, // Left spike
+        Vector2 { x, y: y - 6.0 },  // Top
+        Vector2 { x, y: y + 6.0 },  // Bottom
+        Vector2 { x: x + 10.0, y }, // Right spike
+    ]
+}
+
+pub fn get_magnetic_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 {
+            x: x - 8.0,
+            y: y - 6.0,
+        }, // Left pole outside
+        Vector2 {
+            x: x - 4.0,
+            y: y - 6.0,
+        }, // Left pole inside
+        Vector2 {
+            x: x - 8.0,
+            y: y + 4.0,
+        }, // Left bend outside
+        Vector2 {
+            x: x - 4.0,
+            y: y + 1.0,
+        }, // Left bend inside
+        Vector2 { x, y: y + 6.0 }, // Bottom outside
+        Vector2 { x, y: y + 2.0 }, // Bottom inside
+        Vector2 {
+            x: x + 8.0,
+            y: y + 4.0,
+        }, // Right bend outside
+        Vector2 {
+            x: x + 4.0,
+            y: y + 1.0,
+        }, // Right bend inside
+        Vector2 {
+            x: x + 8.0,
+            y: y - 6.0,
+        }, // Right pole outside
+        Vector2 {
+            x: x + 4.0,
+            y: y - 6.0,
+        }, // Right pole inside
+    ]
+}
+
+pub fn get_tethered_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 { x, y: y - 8.0 }, // Top
+        Vector2 {
+            x: x - 6.0,
+            y: y - 5.0,
+        }, // Upper left
+        Vector2 {
+            x: x + 6.0,
+            y: y - 5.0,
+        }, // Upper right
+        Vector2 { x: x - 8.0, y }, // Left
+        Vector2 { x: x + 8.0, y }, // Right
+        Vector2 {
+            x: x - 6.0,
+            y: y + 5.0,
+        }, // Lower left
+        Vector2 {
+            x: x + 6.0,
+            y: y + 5.0,
+        }, // Lower right
+        Vector2 { x, y: y + 8.0 }, // Bottom, where the chain is fixed
+    ]
+}
+
+pub fn get_timed_mine_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 {
+            x: x - 6.0,
+            y: y - 6.0,
+        }, // Top left
+        Vector2 {
+            x: x - 6.0,
+            y: y + 6.0,
+        }, // Bottom left
+        Vector2 {
+            x: x + 6.0,
+            y: y - 6.0,
+        }, // Top right
+        Vector2 {
+            x: x + 6.0,
+            y: y + 6.0,
+        }, // Bottom right
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MineKind {
+    // Dives from where it was dropped down to the depth of the ship
+    Diving,
+    // Sinks to a fixed depth and goes off when the ship comes close
+    Proximity,
+    // Drifts towards the ship from where it was dropped
+    Magnetic,
+    // Held above the seabed on a chain
+    Tethered,
+    // Dives like the others and goes off when its countdown runs out
+    Timed,
+}
+
+impl MineKind {
+    pub fn random(rng: &mut StdRng) -> Self {
+        match rng.gen_range(0..10) {
+            0..=3 => MineKind::Diving,
+            4..=5 => MineKind::Proximity,
+            6 => MineKind::Magnetic,
+            7..=8 => MineKind::Tethered,
+            _ => MineKind::Timed,
+        }
+    }
+
+    fn vertices(self, x: f32, y: f32) -> Vec<Vector2> {
+        match self {
+            MineKind::Diving => get_mine_vertices(x, y),
+            MineKind::Proximity => get_proximity_mine_vertices(x, y),
+            MineKind::Magnetic => get_magnetic_mine_vertices(x, y),
+            MineKind::Tethered => get_tethered_mine_vertices(x, y),
+            MineKind::Timed => get_timed_mine_vertices(x, y),
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            MineKind::Diving => Color::DARKORANGE,
+            MineKind::Proximity => Color::RED,
+            MineKind::Magnetic => Color::PURPLE,
+            MineKind::Tethered => Color::DARKGREEN,
+            MineKind::Timed => Color::GOLD,
+        }
+    }
+}
+
+const TRAIL_INTERVAL: f32 = 2.5;
+const BLAST_RADIUS: f32 = 90.0;
+const PROXIMITY_RADIUS: f32 = 50.0;
+const SINK_SPEED: f32 = 40.0;
+const MAGNETIC_SPEED: f32 = 45.0;
+const CHAIN_LENGTH: f32 = 70.0;
+const TIMED_COUNTDOWN: f32 = 6.0;
+// Delay before a mine caught in a blast goes off itself
+const FUSE_TIME: f32 = 0.25;
+
 pub struct Mine {
+    kind: MineKind,
     pos: Vector2,
-    launch_x: f32,
     dy: f32,
-    bubble_id: usize,
+    trail_dt: f32,
+    // Proximity mines stop at this depth
+    hold_y: f32,
+    // Set once a tethered mine has sunk down to its chain
+    anchor_y: Option<f32>,
+    countdown: f32,
+    // Lit by a nearby blast
+    fuse: Option<f32>,
+    chain: i32,
+    age: f32,
     arena_x: f32,
+    // on screen, the mine scrolls with the arena
+    prev_screen_pos: Vector2,
     finished: bool,
 }
 
 impl Mine {
-    pub fn new(surface_pos: Vector2, ship: &Ship) -> Self {
+    /// A mine dropped at `screen_pos`, it starts sinking right away.
+    pub fn new(kind: MineKind, screen_pos: Vector2, arena_x: f32, ship: &Ship) -> Self {
         Self {
+            kind,
             pos: Vector2 {
-                x: surface_pos.x - SURFACE_WIDTH as f32 * 0.5,
-                y: surface_pos.y,
+                x: screen_pos.x - arena_x,
+                y: screen_pos.y,
             },
-            launch_x: ship.pos.x + ship.pos.y - (WINDOW_HEIGHT as f32 - surface_pos.y),
             dy: 3.0,
-            bubble_id: 0,
-            arena_x: 0.0,
+            trail_dt: 0.0,
+            hold_y: ship.pos.y,
+            anchor_y: None,
+            countdown: TIMED_COUNTDOWN,
+            fuse: None,
+            chain: 0,
+            age: 0.0,
+            arena_x,
+            prev_screen_pos: screen_pos,
             finished: false,
         }
     }
 
+    pub fn screen_pos(&self) -> Vector2 {
+        Vector2 {
+            x: self.arena_x + self.pos.x,
+            y: self.pos.y,
+        }
+    }
+
     pub fn update(
         &mut self,
         dt: f32,
         arena_x: f32,
-        bubbles_manager: &mut BubblesManager,
+        id: EntityId,
         ship: &Ship,
-        surface_verts: &SurfaceVerts,
+        seabed: &Seabed,
+        commands: &mut Commands,
     ) {
         if self.finished {
             return;
         }
+        self.prev_screen_pos = self.screen_pos();
         self.arena_x = arena_x;
+        self.age += dt;
 
-        if arena_x + self.pos.x < 50.0 {
+        if let Some(fuse) = &mut self.fuse {
+            *fuse -= dt;
+            if *fuse <= 0.0 {
+                self.detonate(commands);
+                return;
+            }
+        }
+
+        if arena_x + self.pos.x < -20.0 {
             self.finished = true;
+            return;
+        }
+
+        if let Some(anchor_y) = self.anchor_y {
+            self.pos.y = anchor_y - CHAIN_LENGTH + (self.age * 2.0).sin() * 4.0;
+            return;
         }
 
-        if arena_x + self.pos.x < self.launch_x {
-            if self.pos.y > ship.pos.y {
-                self.pos.y -= dt * 80.0;
+        match self.kind {
+            MineKind::Proximity => {
+                if self.pos.y < self.hold_y {
+                    self.pos.y = (self.pos.y + dt * SINK_SPEED).min(self.hold_y);
+                }
+                if self.screen_pos().distance_to(ship.center()) < PROXIMITY_RADIUS {
+                    self.detonate(commands);
+                    return;
+                }
+            }
+            MineKind::Magnetic => {
+                let to_ship = ship.center() - self.screen_pos();
+                let dist = to_ship.length();
+                if dist > 1.0 {
+                    self.pos.x += to_ship.x / dist * MAGNETIC_SPEED * dt;
+                    self.pos.y += to_ship.y / dist * MAGNETIC_SPEED * dt;
+                }
+            }
+            MineKind::Tethered => {
+                let floor_y = seabed.floor_y(arena_x + self.pos.x);
+                self.pos.y += dt * SINK_SPEED;
+                if self.pos.y >= floor_y - CHAIN_LENGTH {
+                    self.anchor_y = Some(floor_y);
+                }
             }
-            self.dy = (self.dy * 0.995).max(0.5);
-            self.pos.y += dt * 100.0 * self.dy;
+            _ => {
+                if self.pos.y > ship.pos.y {
+                    self.pos.y -= dt * 80.0;
+                }
+                self.dy = (self.dy * per_frame(0.995, dt)).max(0.5);
+                self.pos.y += dt * 100.0 * self.dy;
+            }
+        }
 
-            if bubbles_manager.is_finished(self.bubble_id) {
-                self.bubble_id = bubbles_manager.insert(Bubbles::new(5));
+        if self.kind == MineKind::Timed {
+            self.countdown -= dt;
+            if self.countdown <= 0.0 {
+                self.detonate(commands);
+                return;
             }
-            bubbles_manager.set_pos(
-                self.bubble_id,
-                Vector2 {
-                    x: arena_x + self.pos.x,
-                    y: self.pos.y,
-                },
-            );
+        }
+
+        // A new trail about when the previous one has risen to the surface
+        self.trail_dt -= dt;
+        if self.trail_dt <= 0.0 {
+            commands.push(Command::AttachBubbles(Bubbles::new(5), Anchor::Mine(id)));
+            self.trail_dt = TRAIL_INTERVAL;
+        }
+    }
+
+    pub fn detonate(&mut self, commands: &mut Commands) {
+        if self.finished {
             return;
         }
+        self.finished = true;
+        commands.push(Command::Explode(Blast {
+            pos: self.screen_pos(),
+            radius: BLAST_RADIUS,
+            chain: self.chain,
+        }));
+    }
 
-        let x = arena_x + self.pos.x;
-        let index = get_surface_verts_index(&surface_verts, x);
-        let y = surface_verts.layer_a[index].y;
-        self.pos.y = y;
+    pub fn is_lit(&self) -> bool {
+        self.fuse.is_some()
+    }
+
+    /// Caught in a blast, the mine goes off after a short delay and carries the chain on.
+    pub fn ignite(&mut self, chain: i32) {
+        if self.finished || self.fuse.is_some() {
+            return;
+        }
+        self.fuse = Some(FUSE_TIME);
+        self.chain = chain;
     }
 
-    fn draw<'d>(&self, mut d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        let vertices = get_mine_vertices(self.arena_x + self.pos.x, self.pos.y);
-        d.draw_triangle_strip(&vertices, Color::DARKORANGE);
-        d
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_screen_pos, self.screen_pos(), alpha);
+        if let Some(anchor_y) = self.anchor_y {
+            d.draw_line(
+                Vector2 {
+                    x: pos.x,
+                    y: pos.y + 8.0,
+                },
+                Vector2 {
+                    x: pos.x,
+                    y: anchor_y,
+                },
+                Color::DARKGRAY,
+            );
+        }
+        let vertices = self.kind.vertices(pos.x, pos.y);
+        d.draw_triangle_strip(&vertices, self.kind.color());
+        if self.kind == MineKind::Timed {
+            let countdown = self.countdown.ceil() as i32;
+            d.draw_text(
+                &countdown.to_string(),
+                pos.x as i32 - 3,
+                pos.y as i32 - 20,
+                10,
+                Color::GOLD,
+            );
+        }
     }
 }
 
 impl Entity for Mine {
-    fn draw<'d>(&self, d: RaylibDrawHandle<'d>) -> RaylibDrawHandle<'d> {
-        self.draw(d)
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
     }
 
     fn is_finished(&self) -> bool {
@@ -120,4 +390,11 @@ impl Entity for Mine {
     }
 }
 
+impl Collider for Mine {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        let pos = self.screen_pos();
+        self.kind.vertices(pos.x, pos.y)
+    }
+}
+
 pub type MineManager = EntityManager<Mine>;
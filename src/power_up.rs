@@ -0,0 +1,228 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::collision::Collider;
+use crate::entity::{Entity, EntityManager};
+use crate::render::*;
+use crate::surface_verts::*;
+use crate::timestep::*;
+
+pub fn get_power_up_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 { x, y: y - 8.0 }, // Top
+        Vector2 { x: x - 8.0, y }, // Left
+        Vector2 { x: x + 8.0, y }, // Right
+        Vector2 { x, y: y + 8.0 }, // Bottom
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PowerUpKind {
+    Speed,
+    Invincible,
+    RapidFire,
+    TripleShot,
+    Shield,
+}
+
+impl PowerUpKind {
+    pub fn random(rng: &mut StdRng) -> Self {
+        match rng.gen_range(0..5) {
+            0 => PowerUpKind::Speed,
+            1 => PowerUpKind::Invincible,
+            2 => PowerUpKind::RapidFire,
+            3 => PowerUpKind::TripleShot,
+            _ => PowerUpKind::Shield,
+        }
+    }
+
+    /// Seconds the effect lasts once picked up.
+    pub fn duration(self) -> f32 {
+        match self {
+            PowerUpKind::Speed => 8.0,
+            PowerUpKind::Invincible => 5.0,
+            PowerUpKind::RapidFire => 8.0,
+            PowerUpKind::TripleShot => 10.0,
+            PowerUpKind::Shield => 12.0,
+        }
+    }
+
+    pub fn letter(self) -> &'static str {
+        match self {
+            PowerUpKind::Speed => "S",
+            PowerUpKind::Invincible => "I",
+            PowerUpKind::RapidFire => "R",
+            PowerUpKind::TripleShot => "T",
+            PowerUpKind::Shield => "H",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::Speed => Color::SKYBLUE,
+            PowerUpKind::Invincible => Color::GOLD,
+            PowerUpKind::RapidFire => Color::ORANGE,
+            PowerUpKind::TripleShot => Color::LIME,
+            PowerUpKind::Shield => Color::VIOLET,
+        }
+    }
+}
+
+const RISE_SPEED: f32 = 30.0;
+// Floats this far below the surface, where the ship can still reach it
+const FLOAT_DEPTH: f32 = 24.0;
+// Damage a shield takes before it breaks
+const SHIELD_STRENGTH: f32 = 60.0;
+
+/// Pickup that comes up from the floor and drifts along with the water.
+pub struct PowerUp {
+    kind: PowerUpKind,
+    pos: Vector2,
+    prev_pos: Vector2,
+    age: f32,
+    finished: bool,
+}
+
+impl PowerUp {
+    pub fn new(kind: PowerUpKind, pos: Vector2) -> Self {
+        Self {
+            kind,
+            pos,
+            prev_pos: pos,
+            age: 0.0,
+            finished: false,
+        }
+    }
+
+    /// `current` is how fast the water moves to the left on screen.
+    pub fn update(&mut self, dt: f32, current: f32, surface_verts: &SurfaceVerts) {
+        if self.finished {
+            return;
+        }
+        self.prev_pos = self.pos;
+        self.age += dt;
+        // Same wobble as a rising bubble
+        let wobble = (self.age * 2.0).sin() * (self.age * 3.0).cos() * 40.0;
+        self.pos.x += (wobble - current) * dt;
+        self.pos.y -= RISE_SPEED * dt;
+        let index = get_surface_verts_index(surface_verts, self.pos.x);
+        self.pos.y = self.pos.y.max(surface_verts.layer_a[index].y + FLOAT_DEPTH);
+        if self.pos.x < -20.0 {
+            self.finished = true;
+        }
+    }
+
+    /// Picked up by the ship, returns what it was the first time only.
+    pub fn collect(&mut self) -> Option<PowerUpKind> {
+        if self.finished {
+            return None;
+        }
+        self.finished = true;
+        Some(self.kind)
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_pos, self.pos, alpha);
+        d.draw_circle(pos, 9.0, Color::WHITE.alpha(0.5));
+        d.draw_triangle_strip(&get_power_up_vertices(pos.x, pos.y), self.kind.color());
+        let width = d.measure_text(self.kind.letter(), 10);
+        d.draw_text(
+            self.kind.letter(),
+            pos.x as i32 - width / 2,
+            pos.y as i32 - 5,
+            10,
+            Color::BLACK,
+        );
+    }
+}
+
+impl Entity for PowerUp {
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn set_pos(&mut self, pos: Vector2) {
+        self.pos = pos;
+    }
+}
+
+impl Collider for PowerUp {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        get_power_up_vertices(self.pos.x, self.pos.y)
+    }
+}
+
+pub type PowerUpManager = EntityManager<PowerUp>;
+
+pub struct Effect {
+    pub kind: PowerUpKind,
+    pub time_left: f32,
+    // What is left of a shield
+    strength: f32,
+}
+
+impl Effect {
+    /// How much of the duration is left, 1.0 when just picked up.
+    pub fn remaining(&self) -> f32 {
+        self.time_left / self.kind.duration()
+    }
+}
+
+/// Power-ups the ship has picked up and that are still running.
+pub struct Effects {
+    active: Vec<Effect>,
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Self { active: vec![] }
+    }
+
+    /// Starts an effect, or starts it over if it is already running.
+    pub fn add(&mut self, kind: PowerUpKind) {
+        self.active.retain(|effect| effect.kind != kind);
+        self.active.push(Effect {
+            kind,
+            time_left: kind.duration(),
+            strength: SHIELD_STRENGTH,
+        });
+    }
+
+    pub fn has(&self, kind: PowerUpKind) -> bool {
+        self.active.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Effect> {
+        self.active.iter()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for effect in &mut self.active {
+            effect.time_left -= dt;
+        }
+        self.active
+            .retain(|effect| effect.time_left > 0.0 && effect.strength > 0.0);
+    }
+
+    /// Returns the part of `amount` that gets through to the hull.
+    pub fn absorb(&mut self, amount: f32) -> f32 {
+        if self.has(PowerUpKind::Invincible) {
+            return 0.0;
+        }
+        let Some(shield) = self
+            .active
+            .iter_mut()
+            .find(|effect| effect.kind == PowerUpKind::Shield)
+        else {
+            return amount;
+        };
+        let absorbed = amount.min(shield.strength);
+        shield.strength -= absorbed;
+        self.active.retain(|effect| effect.strength > 0.0);
+        amount - absorbed
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::io;
+
+use crate::upgrade::*;
+
+const MAGIC: &[u8; 4] = b"DSSP";
+// Version 1 had only the gold, it still loads without upgrades
+const VERSION: u8 = 2;
+
+/// What the player keeps from one run to the next.
+#[derive(Default)]
+pub struct Progress {
+    pub gold: u32,
+    pub upgrades: Upgrades,
+}
+
+impl Progress {
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut data: Vec<u8> = vec![];
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        data.extend_from_slice(&self.gold.to_le_bytes());
+        data.extend_from_slice(&self.upgrades.to_bytes());
+        fs::write(path, data)
+    }
+
+    /// Pays for the next level of `kind`, false if it is maxed out or too expensive.
+    pub fn buy(&mut self, kind: UpgradeKind) -> bool {
+        match self.upgrades.next_cost(kind) {
+            Some(cost) if cost <= self.gold => {
+                self.gold -= cost;
+                self.upgrades.raise(kind);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        if data.len() < 9 || &data[0..4] != MAGIC {
+            return Err(invalid("not a progress file"));
+        }
+        if data[4] != 1 && data[4] != VERSION {
+            return Err(invalid("unsupported progress version"));
+        }
+        let gold = u32::from_le_bytes(data[5..9].try_into().unwrap());
+        let upgrades = Upgrades::from_bytes(&data[9..]);
+        Ok(Self { gold, upgrades })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_bytes(name: &str, data: &[u8]) -> io::Result<Progress> {
+        let path = std::env::temp_dir().join(name);
+        let path = path.to_str().unwrap();
+        fs::write(path, data).unwrap();
+        let result = Progress::load(path);
+        fs::remove_file(path).unwrap();
+        result
+    }
+
+    #[test]
+    fn saved_gold_loads_again() {
+        let path = std::env::temp_dir().join("dss_round_trip.dssp");
+        let path = path.to_str().unwrap();
+        let progress = Progress {
+            gold: 1234,
+            ..Default::default()
+        };
+        progress.save(path).unwrap();
+        let loaded = Progress::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(loaded.gold, 1234);
+    }
+
+    #[test]
+    fn other_files_do_not_load() {
+        assert!(load_bytes("dss_not_progress.dssp", b"DSSR\x01\x00\x00\x00\x00").is_err());
+        assert!(load_bytes("dss_future.dssp", b"DSSP\x63\x00\x00\x00\x00").is_err());
+        assert!(load_bytes("dss_short.dssp", b"DSSP\x01\x00").is_err());
+    }
+
+    #[test]
+    fn version_1_loads_without_upgrades() {
+        let loaded = load_bytes("dss_version_1.dssp", b"DSSP\x01\x2a\x00\x00\x00").unwrap();
+        assert_eq!(loaded.gold, 42);
+        assert_eq!(loaded.upgrades, Upgrades::default());
+    }
+
+    #[test]
+    fn bought_upgrades_load_again() {
+        let mut progress = Progress {
+            gold: 100,
+            ..Default::default()
+        };
+        assert!(progress.buy(UpgradeKind::HullArmour));
+        assert!(progress.buy(UpgradeKind::SonarRange));
+        let path = std::env::temp_dir().join("dss_upgrades.dssp");
+        let path = path.to_str().unwrap();
+        progress.save(path).unwrap();
+        let loaded = Progress::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.gold, progress.gold);
+        assert_eq!(loaded.upgrades, progress.upgrades);
+        assert_eq!(loaded.upgrades.level(UpgradeKind::HullArmour), 1);
+        assert_eq!(loaded.upgrades.level(UpgradeKind::SonarRange), 1);
+    }
+
+    #[test]
+    fn buying_needs_the_gold_and_stops_at_the_max_level() {
+        let mut progress = Progress {
+            gold: 10,
+            ..Default::default()
+        };
+        assert!(!progress.buy(UpgradeKind::HullArmour));
+        assert_eq!(progress.gold, 10);
+
+        progress.gold = 1000;
+        for _ in 0..MAX_LEVEL {
+            assert!(progress.buy(UpgradeKind::AmmoCapacity));
+        }
+        let gold = progress.gold;
+        assert!(!progress.buy(UpgradeKind::AmmoCapacity));
+        assert_eq!(progress.gold, gold);
+        assert_eq!(
+            progress.upgrades.level(UpgradeKind::AmmoCapacity),
+            MAX_LEVEL
+        );
+    }
+}
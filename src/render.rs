@@ -0,0 +1,165 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use raylib::prelude::{RaylibDraw, RaylibDrawHandle};
+
+pub use raylib::prelude::{Color, Vector2};
+
+/// The few drawing primitives the game uses. Raylib implements it for the window,
+/// `RecordingRenderer` implements it for headless runs.
+pub trait Renderer {
+    fn clear_background(&mut self, color: Color);
+    fn draw_triangle_strip(&mut self, points: &[Vector2], color: Color);
+    fn draw_circle(&mut self, center: Vector2, radius: f32, color: Color);
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color);
+    fn draw_line(&mut self, start: Vector2, end: Vector2, color: Color);
+    fn measure_text(&self, text: &str, font_size: i32) -> i32;
+}
+
+impl Renderer for RaylibDrawHandle<'_> {
+    fn clear_background(&mut self, color: Color) {
+        RaylibDraw::clear_background(self, color);
+    }
+
+    fn draw_triangle_strip(&mut self, points: &[Vector2], color: Color) {
+        RaylibDraw::draw_triangle_strip(self, points, color);
+    }
+
+    fn draw_circle(&mut self, center: Vector2, radius: f32, color: Color) {
+        self.draw_circle_v(center, radius, color);
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+        RaylibDraw::draw_text(self, text, x, y, font_size, color);
+    }
+
+    fn draw_line(&mut self, start: Vector2, end: Vector2, color: Color) {
+        self.draw_line_v(start, end, color);
+    }
+
+    fn measure_text(&self, text: &str, font_size: i32) -> i32 {
+        raylib::prelude::measure_text(text, font_size)
+    }
+}
+
+pub enum DrawCommand {
+    Clear(Color),
+    TriangleStrip(Vec<Vector2>, Color),
+    Circle(Vector2, f32, Color),
+    Text(String, i32, i32, i32, Color),
+    Line(Vector2, Vector2, Color),
+}
+
+/// Keeps every draw call of a frame instead of putting pixels on a screen.
+pub struct RecordingRenderer {
+    pub commands: Vec<DrawCommand>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        Self { commands: vec![] }
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    // Two runs that draw the same frame produce the same digest
+    pub fn digest(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let hash_vec = |v: &Vector2, hasher: &mut DefaultHasher| {
+            v.x.to_bits().hash(hasher);
+            v.y.to_bits().hash(hasher);
+        };
+        let hash_color = |c: &Color, hasher: &mut DefaultHasher| {
+            (c.r, c.g, c.b, c.a).hash(hasher);
+        };
+        for command in &self.commands {
+            match command {
+                DrawCommand::Clear(color) => {
+                    0.hash(&mut hasher);
+                    hash_color(color, &mut hasher);
+                }
+                DrawCommand::TriangleStrip(points, color) => {
+                    1.hash(&mut hasher);
+                    for point in points {
+                        hash_vec(point, &mut hasher);
+                    }
+                    hash_color(color, &mut hasher);
+                }
+                DrawCommand::Circle(center, radius, color) => {
+                    2.hash(&mut hasher);
+                    hash_vec(center, &mut hasher);
+                    radius.to_bits().hash(&mut hasher);
+                    hash_color(color, &mut hasher);
+                }
+                DrawCommand::Text(text, x, y, font_size, color) => {
+                    3.hash(&mut hasher);
+                    text.hash(&mut hasher);
+                    (x, y, font_size).hash(&mut hasher);
+                    hash_color(color, &mut hasher);
+                }
+                DrawCommand::Line(start, end, color) => {
+                    4.hash(&mut hasher);
+                    hash_vec(start, &mut hasher);
+                    hash_vec(end, &mut hasher);
+                    hash_color(color, &mut hasher);
+                }
+            }
+        }
+        hasher.finish()
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn clear_background(&mut self, color: Color) {
+        self.commands.push(DrawCommand::Clear(color));
+    }
+
+    fn draw_triangle_strip(&mut self, points: &[Vector2], color: Color) {
+        self.commands
+            .push(DrawCommand::TriangleStrip(points.to_vec(), color));
+    }
+
+    fn draw_circle(&mut self, center: Vector2, radius: f32, color: Color) {
+        self.commands
+            .push(DrawCommand::Circle(center, radius, color));
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+        self.commands
+            .push(DrawCommand::Text(text.to_string(), x, y, font_size, color));
+    }
+
+    fn draw_line(&mut self, start: Vector2, end: Vector2, color: Color) {
+        self.commands.push(DrawCommand::Line(start, end, color));
+    }
+
+    // Roughly the width of raylib's default font
+    fn measure_text(&self, text: &str, font_size: i32) -> i32 {
+        text.len() as i32 * font_size * 3 / 5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw_frame(d: &mut dyn Renderer) {
+        d.clear_background(Color::BLUE);
+        d.draw_circle(Vector2 { x: 1.0, y: 2.0 }, 3.0, Color::RED);
+        d.draw_text("SCORE", 10, 10, 20, Color::WHITE);
+    }
+
+    #[test]
+    fn same_frame_gives_the_same_digest() {
+        let mut a = RecordingRenderer::new();
+        let mut b = RecordingRenderer::new();
+        draw_frame(&mut a);
+        draw_frame(&mut b);
+        assert_eq!(a.digest(), b.digest());
+
+        b.draw_line(Vector2::zero(), Vector2 { x: 5.0, y: 5.0 }, Color::WHITE);
+        assert_ne!(a.digest(), b.digest());
+    }
+}
@@ -0,0 +1,251 @@
+use std::fs;
+use std::io;
+
+use crate::game::Input;
+use crate::upgrade::*;
+
+const MAGIC: &[u8; 4] = b"DSSR";
+// Version 2 added the upgrades, version 1 replays play without them
+const VERSION: u8 = 2;
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const FIRE: u8 = 4;
+const LEFT: u8 = 8;
+const RIGHT: u8 = 16;
+const SWITCH_WEAPON: u8 = 32;
+
+fn encode(input: &Input) -> u8 {
+    let mut bits = 0;
+    if input.up {
+        bits |= UP;
+    }
+    if input.down {
+        bits |= DOWN;
+    }
+    if input.fire {
+        bits |= FIRE;
+    }
+    if input.left {
+        bits |= LEFT;
+    }
+    if input.right {
+        bits |= RIGHT;
+    }
+    if input.switch_weapon {
+        bits |= SWITCH_WEAPON;
+    }
+    bits
+}
+
+fn decode(bits: u8) -> Input {
+    Input {
+        up: bits & UP != 0,
+        down: bits & DOWN != 0,
+        fire: bits & FIRE != 0,
+        left: bits & LEFT != 0,
+        right: bits & RIGHT != 0,
+        switch_weapon: bits & SWITCH_WEAPON != 0,
+        ..Default::default()
+    }
+}
+
+/// The seed and upgrades of a run plus the held keys of every tick, run-length
+/// encoded because the keys rarely change from one tick to the next.
+pub struct InputLog {
+    seed: u64,
+    upgrades: Upgrades,
+    runs: Vec<(u8, u16)>,
+}
+
+impl InputLog {
+    pub fn new(seed: u64, upgrades: Upgrades) -> Self {
+        Self {
+            seed,
+            upgrades,
+            runs: vec![],
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn upgrades(&self) -> Upgrades {
+        self.upgrades
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    pub fn push(&mut self, input: &Input) {
+        let bits = encode(input);
+        match self.runs.last_mut() {
+            Some((last_bits, count)) if *last_bits == bits && *count < u16::MAX => *count += 1,
+            _ => self.runs.push((bits, 1)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut data: Vec<u8> = vec![];
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        data.extend_from_slice(&self.seed.to_le_bytes());
+        data.extend_from_slice(&self.upgrades.to_bytes());
+        for (bits, count) in &self.runs {
+            data.push(*bits);
+            data.extend_from_slice(&count.to_le_bytes());
+        }
+        fs::write(path, data)
+    }
+
+    pub fn load(path: &str) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        if data.len() < 13 || &data[0..4] != MAGIC {
+            return Err(invalid("not a replay file"));
+        }
+        let header_len = match data[4] {
+            1 => 13,
+            VERSION => 13 + UPGRADE_KINDS.len(),
+            _ => return Err(invalid("unsupported replay version")),
+        };
+        if data.len() < header_len {
+            return Err(invalid("truncated replay file"));
+        }
+        let seed = u64::from_le_bytes(data[5..13].try_into().unwrap());
+        let upgrades = Upgrades::from_bytes(&data[13..header_len]);
+        let body = &data[header_len..];
+        if body.len() % 3 != 0 {
+            return Err(invalid("truncated replay file"));
+        }
+        let runs = body
+            .chunks(3)
+            .map(|chunk| (chunk[0], u16::from_le_bytes([chunk[1], chunk[2]])))
+            .collect();
+        Ok(Self {
+            seed,
+            upgrades,
+            runs,
+        })
+    }
+}
+
+/// Hands out the recorded input tick by tick.
+pub struct Playback {
+    log: InputLog,
+    run: usize,
+    tick_in_run: u16,
+}
+
+impl Playback {
+    pub fn new(log: InputLog) -> Self {
+        Self {
+            log,
+            run: 0,
+            tick_in_run: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.log.seed()
+    }
+
+    pub fn upgrades(&self) -> Upgrades {
+        self.log.upgrades()
+    }
+}
+
+impl Iterator for Playback {
+    type Item = Input;
+
+    fn next(&mut self) -> Option<Input> {
+        let (bits, count) = *self.log.runs.get(self.run)?;
+        self.tick_in_run += 1;
+        if self.tick_in_run >= count {
+            self.run += 1;
+            self.tick_in_run = 0;
+        }
+        Some(decode(bits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_log_loads_the_same_ticks() {
+        let held = Input {
+            up: true,
+            fire: true,
+            ..Default::default()
+        };
+        let inputs = [
+            Input::default(),
+            held,
+            held,
+            Input {
+                down: true,
+                ..Default::default()
+            },
+        ];
+        let mut log = InputLog::new(1234, Upgrades::default());
+        for input in &inputs {
+            log.push(input);
+        }
+        let path = std::env::temp_dir().join("dss_round_trip.dssr");
+        let path = path.to_str().unwrap();
+        log.save(path).unwrap();
+        let loaded = InputLog::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.seed(), 1234);
+        let played: Vec<u8> = Playback::new(loaded).map(|input| encode(&input)).collect();
+        let expected: Vec<u8> = inputs.iter().map(encode).collect();
+        assert_eq!(played, expected);
+    }
+
+    #[test]
+    fn other_files_do_not_load() {
+        let path = std::env::temp_dir().join("dss_not_a_replay.dssr");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, b"DSSP\x02 not a replay").unwrap();
+        let result = InputLog::load(path);
+        std::fs::remove_file(path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn upgrades_load_with_the_log() {
+        let mut upgrades = Upgrades::default();
+        upgrades.raise(UpgradeKind::EngineSpeed);
+        let mut log = InputLog::new(5, upgrades);
+        log.push(&Input::default());
+        let path = std::env::temp_dir().join("dss_upgrades.dssr");
+        let path = path.to_str().unwrap();
+        log.save(path).unwrap();
+        let loaded = InputLog::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.upgrades(), upgrades);
+    }
+
+    #[test]
+    fn version_1_plays_without_upgrades() {
+        let mut data: Vec<u8> = vec![];
+        data.extend_from_slice(MAGIC);
+        data.push(1);
+        data.extend_from_slice(&5u64.to_le_bytes());
+        data.extend_from_slice(&[UP, 3, 0]);
+        let path = std::env::temp_dir().join("dss_version_1.dssr");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, data).unwrap();
+        let loaded = InputLog::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.seed(), 5);
+        assert_eq!(loaded.upgrades(), Upgrades::default());
+        assert_eq!(Playback::new(loaded).filter(|input| input.up).count(), 3);
+    }
+}
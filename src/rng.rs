@@ -0,0 +1,44 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// All randomness of a run comes from one seed. Every subsystem draws from its
+/// own stream, so an extra random call in one place doesn't shift the others.
+pub struct GameRng {
+    seed: u64,
+    pub water: StdRng,
+    pub fish: StdRng,
+    pub bubbles: StdRng,
+    pub effects: StdRng,
+    pub terrain: StdRng,
+    pub mines: StdRng,
+    pub enemies: StdRng,
+    pub loot: StdRng,
+}
+
+fn stream(seed: u64, index: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ index.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            water: stream(seed, 1),
+            fish: stream(seed, 2),
+            bubbles: stream(seed, 3),
+            effects: stream(seed, 4),
+            terrain: stream(seed, 5),
+            mines: stream(seed, 6),
+            enemies: stream(seed, 7),
+            loot: stream(seed, 8),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
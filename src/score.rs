@@ -0,0 +1,91 @@
+use crate::treasure::TreasureKind;
+
+pub const MAX_HULL: f32 = 100.0;
+pub const START_LIVES: i32 = 3;
+
+const MINE_POINTS: i32 = 100;
+const FISH_POINTS: i32 = 10;
+const WARSHIP_POINTS: i32 = 300;
+const SUB_POINTS: i32 = 200;
+const BOSS_POINTS: i32 = 2000;
+// Per mine in a chain reaction, times its place in the chain
+const CHAIN_POINTS: i32 = 50;
+const PIXELS_PER_METER: f32 = 10.0;
+
+pub struct Score {
+    pub distance: f32,
+    pub mines_destroyed: i32,
+    pub fish_hit: i32,
+    pub warships_sunk: i32,
+    pub subs_sunk: i32,
+    pub bosses_defeated: i32,
+    chain_points: i32,
+    treasure_points: i32,
+    // Collected this run, banked when the run ends
+    pub gold: i32,
+    pub best_chain: i32,
+    pub lives: i32,
+    pub hull: f32,
+}
+
+impl Score {
+    pub fn new() -> Self {
+        Self {
+            distance: 0.0,
+            mines_destroyed: 0,
+            fish_hit: 0,
+            warships_sunk: 0,
+            subs_sunk: 0,
+            bosses_defeated: 0,
+            chain_points: 0,
+            treasure_points: 0,
+            gold: 0,
+            best_chain: 0,
+            lives: START_LIVES,
+            hull: MAX_HULL,
+        }
+    }
+
+    pub fn update(&mut self, arena_x: f32) {
+        self.distance = self.distance.max(-arena_x / PIXELS_PER_METER);
+    }
+
+    pub fn points(&self) -> i32 {
+        self.distance as i32
+            + self.mines_destroyed * MINE_POINTS
+            + self.fish_hit * FISH_POINTS
+            + self.warships_sunk * WARSHIP_POINTS
+            + self.subs_sunk * SUB_POINTS
+            + self.bosses_defeated * BOSS_POINTS
+            + self.chain_points
+            + self.treasure_points
+    }
+
+    /// A mine set off by another blast, `chain` is its place in the chain.
+    pub fn chain_kill(&mut self, chain: i32) {
+        self.mines_destroyed += 1;
+        self.chain_points += CHAIN_POINTS * chain;
+        self.best_chain = self.best_chain.max(chain);
+    }
+
+    pub fn collect(&mut self, kind: TreasureKind) {
+        self.treasure_points += kind.points();
+        self.gold += kind.gold();
+    }
+
+    // A life is lost when the hull breaks, the next one starts with a fresh hull
+    pub fn damage(&mut self, amount: f32) {
+        if self.lives == 0 {
+            return;
+        }
+        self.hull -= amount;
+        if self.hull <= 0.0 {
+            self.lives -= 1;
+            self.hull = if self.lives > 0 { MAX_HULL } else { 0.0 };
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.lives == 0
+    }
+}
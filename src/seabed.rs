@@ -0,0 +1,248 @@
+use std::f32;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::consts::*;
+use crate::render::*;
+use crate::surface_verts::ease_in_out_quad;
+use crate::water::*;
+
+const FLOOR_MIN: f32 = 15.0;
+// Open water always left between the lowest the ship can be pushed by the
+// surface and the rocks, so there is a way through everything
+const MIN_GAP: f32 = 90.0;
+const SURFACE_CLEARANCE: f32 = 30.0;
+const CALM_SEGMENTS: i32 = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Feature {
+    Rocks,
+    Trench,
+    Narrows,
+    Cave,
+}
+
+/// Heights are measured up from the bottom of the window, like `Surface::pos.y`.
+struct Segment {
+    x: f32,
+    floor: f32,
+    // Rock hanging over the floor, no rock when both are the same
+    rock_low: f32,
+    rock_high: f32,
+}
+
+pub struct SeabedVerts {
+    pub floor: Vec<Vector2>,
+    pub rock: Vec<Vector2>,
+}
+
+impl SeabedVerts {
+    pub fn new() -> Self {
+        Self {
+            floor: vec![],
+            rock: vec![],
+        }
+    }
+}
+
+fn get_seabed_verts(segments: &[Segment], x: f32) -> SeabedVerts {
+    let mut floor: Vec<Vector2> = vec![];
+    let mut rock: Vec<Vector2> = vec![];
+    let bottom = WINDOW_HEIGHT as f32;
+    let mut last: Option<&Segment> = None;
+    for segment in segments {
+        let Some(prev) = last else {
+            last = Some(segment);
+            continue;
+        };
+        for j in 0..8 {
+            let xd = (j as f32) / 7.0;
+            let ei = ease_in_out_quad(xd);
+            let x1 = x + prev.x + (segment.x - prev.x) * xd;
+            let floor1 = prev.floor + (segment.floor - prev.floor) * ei;
+            let rock_low1 = prev.rock_low + (segment.rock_low - prev.rock_low) * ei;
+            let rock_high1 = prev.rock_high + (segment.rock_high - prev.rock_high) * ei;
+
+            floor.push(Vector2 {
+                x: x1,
+                y: bottom - floor1,
+            });
+            floor.push(Vector2 { x: x1, y: bottom });
+
+            rock.push(Vector2 {
+                x: x1,
+                y: bottom - rock_high1,
+            });
+            rock.push(Vector2 {
+                x: x1,
+                y: bottom - rock_low1,
+            });
+        }
+        last = Some(segment);
+    }
+    SeabedVerts { floor, rock }
+}
+
+// Index of the left pair of vertices around `x` and how far `x` is towards the right pair
+fn strip_pos(strip: &[Vector2], x: f32) -> Option<(usize, f32)> {
+    let pairs = strip.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let pair = strip
+        .chunks(2)
+        .position(|pair| pair[0].x > x)
+        .unwrap_or(pairs)
+        .clamp(1, pairs - 1)
+        - 1;
+    let x0 = strip[pair * 2].x;
+    let x1 = strip[pair * 2 + 2].x;
+    if x1 <= x0 {
+        return Some((pair * 2, 0.0));
+    }
+    Some((pair * 2, ((x - x0) / (x1 - x0)).clamp(0.0, 1.0)))
+}
+
+fn strip_y(strip: &[Vector2], index: usize, t: f32) -> f32 {
+    strip[index].y + (strip[index + 2].y - strip[index].y) * t
+}
+
+/// Scrolling sea floor with rocks, trenches, narrow passages and caves, made
+/// segment by segment alongside the water surface.
+pub struct Seabed {
+    segments: Vec<Segment>,
+    feature: Feature,
+    feature_left: i32,
+    pub verts: SeabedVerts,
+    arena_x: f32,
+    prev_arena_x: f32,
+}
+
+impl Seabed {
+    pub fn new() -> Self {
+        Self {
+            segments: vec![Segment {
+                x: 0.0,
+                floor: FLOOR_MIN,
+                rock_low: 0.0,
+                rock_high: 0.0,
+            }],
+            feature: Feature::Rocks,
+            feature_left: CALM_SEGMENTS,
+            verts: SeabedVerts::new(),
+            arena_x: 0.0,
+            prev_arena_x: 0.0,
+        }
+    }
+
+    fn next_feature(&mut self, rng: &mut StdRng) {
+        self.feature = match rng.gen_range(0..20) {
+            0..=9 => Feature::Rocks,
+            10..=12 => Feature::Trench,
+            13..=15 => Feature::Narrows,
+            _ => Feature::Cave,
+        };
+        self.feature_left = rng.gen_range(2..6);
+    }
+
+    fn next_segment(&mut self, x: f32, depth: f32, rng: &mut StdRng) -> Segment {
+        if self.feature_left <= 0 {
+            self.next_feature(rng);
+        }
+        self.feature_left -= 1;
+
+        let prev_floor = self.segments[self.segments.len() - 1].floor;
+        let floor_max = (depth - SURFACE_CLEARANCE - MIN_GAP).max(FLOOR_MIN);
+        let rocks = (prev_floor + rng.gen_range(-25.0..25.0)).clamp(FLOOR_MIN, floor_max.min(80.0));
+        let floor = match self.feature {
+            Feature::Rocks => rocks,
+            Feature::Trench => FLOOR_MIN,
+            Feature::Narrows => floor_max,
+            Feature::Cave => rocks.min(40.0),
+        };
+
+        // A cave needs room for the passage below the rock and the rock itself
+        let rock_low = floor + MIN_GAP;
+        let rock_room = depth - SURFACE_CLEARANCE - rock_low;
+        if self.feature == Feature::Cave && rock_room > 20.0 {
+            return Segment {
+                x,
+                floor,
+                rock_low,
+                rock_high: rock_low + rng.gen_range(20.0..rock_room.min(60.0)),
+            };
+        }
+        Segment {
+            x,
+            floor,
+            rock_low,
+            rock_high: rock_low,
+        }
+    }
+
+    pub fn update(&mut self, arena_x: f32, water: &Water, rng: &mut StdRng) {
+        self.prev_arena_x = self.arena_x;
+        self.arena_x = arena_x;
+        loop {
+            let segment_x = self.segments[self.segments.len() - 1].x;
+            if arena_x + segment_x >= WINDOW_WIDTH as f32 {
+                break;
+            }
+            let x = segment_x + SURFACE_WIDTH as f32;
+            let depth = water.surface_height(x).unwrap_or(150.0);
+            let segment = self.next_segment(x, depth, rng);
+            self.segments.push(segment);
+        }
+        // Keep one segment left of the screen to ease in from
+        while self.segments.len() > 2 && arena_x + self.segments[1].x < -(SURFACE_WIDTH as f32) {
+            self.segments.remove(0);
+        }
+        self.verts = get_seabed_verts(&self.segments, arena_x);
+    }
+
+    /// Screen y of the sea floor at screen `x`.
+    pub fn floor_y(&self, x: f32) -> f32 {
+        match strip_pos(&self.verts.floor, x) {
+            Some((index, t)) => strip_y(&self.verts.floor, index, t),
+            None => WINDOW_HEIGHT as f32,
+        }
+    }
+
+    /// Top and bottom screen y of overhanging rock at screen `x`.
+    pub fn rock_span(&self, x: f32) -> Option<(f32, f32)> {
+        let (index, t) = strip_pos(&self.verts.rock, x)?;
+        let top = strip_y(&self.verts.rock, index, t);
+        let bottom = strip_y(&self.verts.rock, index + 1, t);
+        if bottom - top < 0.5 {
+            return None;
+        }
+        Some((top, bottom))
+    }
+
+    pub fn is_rock(&self, x: f32, y: f32) -> bool {
+        self.push_out(x, y, 0.0) != y
+    }
+
+    /// `y` moved out of the rocks to the nearest side, keeping `clearance`.
+    pub fn push_out(&self, x: f32, y: f32, clearance: f32) -> f32 {
+        let mut y = y;
+        if let Some((top, bottom)) = self.rock_span(x) {
+            if y > top - clearance && y < bottom + clearance {
+                y = if y - top < bottom - y {
+                    top - clearance
+                } else {
+                    bottom + clearance
+                };
+            }
+        }
+        y.min(self.floor_y(x) - clearance)
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let arena_x = self.prev_arena_x + (self.arena_x - self.prev_arena_x) * alpha;
+        let verts = get_seabed_verts(&self.segments, arena_x);
+        d.draw_triangle_strip(&verts.floor, Color::DARKBROWN);
+        d.draw_triangle_strip(&verts.rock, Color::BROWN);
+    }
+}
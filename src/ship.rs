@@ -1,9 +1,18 @@
-use raylib::prelude::*;
+use std::ops::Range;
 
 use crate::bubbles::*;
 use crate::bullet::*;
+use crate::collision::Collider;
+use crate::commands::*;
 use crate::consts::*;
+use crate::game::Input;
+use crate::power_up::*;
+use crate::render::*;
+use crate::seabed::*;
 use crate::surface_verts::*;
+use crate::timestep::*;
+use crate::upgrade::*;
+use crate::weapon::*;
 
 pub fn get_ship_vertices(x: f32, y: f32) -> Vec<Vector2> {
     vec![
@@ -43,67 +52,405 @@ pub fn get_ship_vertices(x: f32, y: f32) -> Vec<Vector2> {
     ]
 }
 
+/// Part of the hull that takes damage on its own.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Section {
+    Rear,
+    MidBody,
+    Cannon,
+    Cockpit,
+}
+
+const SECTIONS: [Section; 4] = [
+    Section::Rear,
+    Section::MidBody,
+    Section::Cannon,
+    Section::Cockpit,
+];
+
+impl Section {
+    // The run of `get_ship_vertices` that draws this part of the strip
+    fn vertex_range(self) -> Range<usize> {
+        match self {
+            Section::Rear => 0..4,
+            Section::MidBody => 2..6,
+            Section::Cannon => 4..8,
+            Section::Cockpit => 6..9,
+        }
+    }
+}
+
+// Acceleration while a direction key is held, top speed comes from the drag
+const SHIP_THRUST: f32 = 220.0;
+const SHIP_DRAG: f32 = 0.97;
+const SHIP_PUSH_BACK_SPEED: f32 = 120.0;
+// The hull floats up when shallower than this below the surface and sinks when deeper
+const NEUTRAL_DEPTH: f32 = 120.0;
+const BUOYANCY: f32 = 15.0;
+const TORPEDO_SPEED: f32 = 300.0;
+const HOMING_SPEED: f32 = 200.0;
+const DEPTH_CHARGE_SINK_SPEED: f32 = 60.0;
+// Angle between the shots of a spread
+const SPREAD_ANGLE: f32 = 0.25;
+// Sideways speed between the depth charges of a triple shot
+const DEPTH_CHARGE_FAN_SPEED: f32 = 40.0;
+const SPEED_BOOST: f32 = 1.6;
+const RAPID_FIRE_COOLDOWN: f32 = 0.35;
+pub const MAX_AIR: f32 = 100.0;
+// Per second while under water, a full tank lasts 25 seconds
+const AIR_DRAIN: f32 = 4.0;
+// Per second while up in the surface band
+const AIR_REFILL: f32 = 40.0;
+// How far below the shallowest the ship can go that still counts as surfaced
+const SURFACE_BAND: f32 = 10.0;
+const SECTION_INTEGRITY: f32 = 40.0;
+// Below this part of its integrity a section shows cracks and leaks
+const SECTION_DAMAGED: f32 = 0.6;
+// Seconds between two bubble trails out of each damaged section
+const LEAK_INTERVAL: f32 = 1.5;
+const LEAK_BUBBLES: usize = 4;
+// What is left of the thrust with a broken rear or mid body
+const BROKEN_REAR_THRUST: f32 = 0.6;
+const BROKEN_MID_BODY_THRUST: f32 = 0.8;
+// A broken cannon fires slower, a broken cockpit reloads slower
+const BROKEN_CANNON_COOLDOWN: f32 = 2.0;
+const BROKEN_COCKPIT_RELOAD: f32 = 0.5;
+const SHIP_X_MIN: f32 = 10.0;
+// Room kept in front of the ship at the right edge of the window
+const SHIP_X_MARGIN: f32 = 60.0;
+
 pub struct Ship {
     pub pos: Vector2,
-    bubbles_id: usize,
-    bullet_id: usize,
+    prev_pos: Vector2,
+    vel: Vector2,
     y_ofs: f32,
+    // Where the hull touched rock this tick
+    scrape_pos: Option<Vector2>,
+    integrity: [f32; 4],
+    leak_dt: f32,
+    // From the upgrades bought in the shop
+    armour: f32,
+    thrust_scale: f32,
+    sonar_range: f32,
+    pub weapons: Weapons,
+    pub effects: Effects,
+    pub air: f32,
 }
 
 impl Ship {
-    pub fn new() -> Self {
+    pub fn new(upgrades: &Upgrades) -> Self {
+        let pos = Vector2 {
+            x: 100.0,
+            y: WINDOW_HEIGHT as f32 - 100.0,
+        };
         Self {
-            pos: Vector2 {
-                x: 100.0,
-                y: WINDOW_HEIGHT as f32 - 100.0,
-            },
-            bubbles_id: 0,
-            bullet_id: 0,
+            pos,
+            prev_pos: pos,
+            vel: Vector2::zero(),
             y_ofs: 0.0,
+            scrape_pos: None,
+            integrity: [SECTION_INTEGRITY; 4],
+            leak_dt: 0.0,
+            armour: upgrades.armour(),
+            thrust_scale: upgrades.thrust(),
+            sonar_range: upgrades.sonar_range(),
+            weapons: Weapons::new(upgrades.reload_speed(), upgrades.extra_ammo()),
+            effects: Effects::new(),
+            air: MAX_AIR,
         }
     }
 
-    pub fn update(&mut self, bubbles_manager: &mut BubblesManager, surface_verts: &SurfaceVerts) {
-        let ship_index = get_surface_verts_index(&surface_verts, self.pos.x);
+    pub fn update(
+        &mut self,
+        dt: f32,
+        input: &Input,
+        surface_verts: &SurfaceVerts,
+        seabed: &Seabed,
+        commands: &mut Commands,
+    ) {
+        self.prev_pos = self.pos;
+
+        if input.switch_weapon {
+            self.weapons.switch();
+        }
+        if self.is_broken(Section::Cockpit) {
+            self.weapons.update(dt * BROKEN_COCKPIT_RELOAD);
+        } else {
+            self.weapons.update(dt);
+        }
+        self.effects.update(dt);
+
+        self.leak_dt += dt;
+        if self.leak_dt >= LEAK_INTERVAL {
+            self.leak_dt -= LEAK_INTERVAL;
+            for section in SECTIONS {
+                if self.is_damaged(section) {
+                    commands.push(Command::AttachBubbles(
+                        Bubbles::new(LEAK_BUBBLES),
+                        Anchor::ShipSection(section),
+                    ));
+                }
+            }
+        }
+
+        let ship_index = get_surface_verts_index(surface_verts, self.pos.x);
         self.y_ofs = surface_verts.layer_c[ship_index].y - surface_verts.layer_b[ship_index].y;
+        let depth = self.pos.y - surface_verts.layer_a[ship_index].y;
+
+        let mut thrust = SHIP_THRUST * self.thrust_scale;
+        if self.effects.has(PowerUpKind::Speed) {
+            thrust *= SPEED_BOOST;
+        }
+        if self.is_broken(Section::Rear) {
+            thrust *= BROKEN_REAR_THRUST;
+        }
+        if self.is_broken(Section::MidBody) {
+            thrust *= BROKEN_MID_BODY_THRUST;
+        }
+        let mut accel = Vector2 {
+            x: 0.0,
+            y: -BUOYANCY * (1.0 - depth / NEUTRAL_DEPTH).clamp(-1.0, 1.0),
+        };
+        if input.up {
+            accel.y -= thrust;
+        }
+        if input.down {
+            accel.y += thrust;
+        }
+        if input.left {
+            accel.x -= thrust;
+        }
+        if input.right {
+            accel.x += thrust;
+        }
+        let drag = per_frame(SHIP_DRAG, dt);
+        self.vel.x = (self.vel.x + accel.x * dt) * drag;
+        self.vel.y = (self.vel.y + accel.y * dt) * drag;
+        self.pos.x += self.vel.x * dt;
+        self.pos.y += self.vel.y * dt;
+
+        let ship_x_max = WINDOW_WIDTH as f32 - SHIP_X_MARGIN;
+        if self.pos.x < SHIP_X_MIN || self.pos.x > ship_x_max {
+            self.pos.x = self.pos.x.clamp(SHIP_X_MIN, ship_x_max);
+            self.vel.x = 0.0;
+        }
 
         let ship_y_min = surface_verts.layer_a[ship_index].y + 30.0;
-        let ship_y_max = WINDOW_HEIGHT as f32 - 30.0;
         if self.pos.y < ship_y_min {
             let diff = ship_y_min - self.pos.y;
-            self.pos.y += diff.min(2.0);
+            self.pos.y += diff.min(dt * SHIP_PUSH_BACK_SPEED);
+            self.vel.y = self.vel.y.max(0.0);
+        }
+        if self.pos.y < ship_y_min + SURFACE_BAND {
+            self.air = (self.air + AIR_REFILL * dt).min(MAX_AIR);
+        } else {
+            self.air = (self.air - AIR_DRAIN * dt).max(0.0);
+        }
+
+        // Rocks don't give way, the ship scrapes along them
+        let mut push = 0.0f32;
+        self.scrape_pos = None;
+        for v in get_ship_vertices(self.pos.x, self.pos.y) {
+            let dy = seabed.push_out(v.x, v.y, 0.0) - v.y;
+            if dy.abs() > push.abs() {
+                push = dy;
+                self.scrape_pos = Some(v);
+            }
+        }
+        self.pos.y += push;
+        if push * self.vel.y < 0.0 {
+            self.vel.y = 0.0;
         }
-        if self.pos.y > ship_y_max {
-            let diff = self.pos.y - ship_y_max;
-            self.pos.y -= diff.min(2.0);
+    }
+
+    pub fn is_out_of_air(&self) -> bool {
+        self.air <= 0.0
+    }
+
+    /// Where the hull scrapes along rock, if it does.
+    pub fn scrape_pos(&self) -> Option<Vector2> {
+        self.scrape_pos
+    }
+
+    fn is_damaged(&self, section: Section) -> bool {
+        self.integrity[section as usize] < SECTION_INTEGRITY * SECTION_DAMAGED
+    }
+
+    fn is_broken(&self, section: Section) -> bool {
+        self.integrity[section as usize] <= 0.0
+    }
+
+    /// Middle of a section on screen, leaks come out there.
+    pub fn section_pos(&self, section: Section) -> Vector2 {
+        section_center(&get_ship_vertices(self.pos.x, self.pos.y), section)
+    }
+
+    /// A new life comes with a fresh hull and a full tank.
+    pub fn refit(&mut self) {
+        self.integrity = [SECTION_INTEGRITY; 4];
+        self.air = MAX_AIR;
+    }
+
+    pub fn center(&self) -> Vector2 {
+        Vector2 {
+            x: self.pos.x + 22.5,
+            y: self.pos.y,
         }
+    }
 
-        bubbles_manager.set_pos(
-            self.bubbles_id,
-            Vector2 {
-                x: self.pos.x + 10.0,
-                y: self.pos.y,
-            },
-        );
+    /// Where attached bubbles come out.
+    pub fn exhaust_pos(&self) -> Vector2 {
+        Vector2 {
+            x: self.pos.x + 10.0,
+            y: self.pos.y,
+        }
     }
 
-    pub fn draw<'a>(&mut self, mut d: RaylibDrawHandle<'a>) -> RaylibDrawHandle<'a> {
+    /// Damage coming from `from`, the section closest to it takes it. Returns the part
+    /// of `amount` that gets through the power-ups to the hull.
+    pub fn take_damage(&mut self, amount: f32, from: Vector2) -> f32 {
+        let amount = self.effects.absorb(amount) * self.armour;
         let vertices = get_ship_vertices(self.pos.x, self.pos.y);
-        d.draw_triangle_strip(&vertices, Color::WHITESMOKE);
-        d
+        let closest = SECTIONS
+            .into_iter()
+            .min_by(|a, b| {
+                let dist_a = section_center(&vertices, *a).distance_to(from);
+                let dist_b = section_center(&vertices, *b).distance_to(from);
+                dist_a.total_cmp(&dist_b)
+            })
+            .unwrap();
+        let integrity = &mut self.integrity[closest as usize];
+        *integrity = (*integrity - amount).max(0.0);
+        amount
     }
 
-    pub fn start_bullet(
-        &mut self,
-        bubbles_manager: &mut BubblesManager,
-        bullet_manager: &mut BulletManager,
-    ) {
-        if bullet_manager.is_finished(self.bullet_id) {
-            self.bullet_id = bullet_manager.insert(Bullet::new(Vector2 {
-                x: self.pos.x + 15.0,
-                y: self.pos.y + 10.0,
-            }));
-            self.bubbles_id = bubbles_manager.insert(Bubbles::new(20));
+    pub fn draw(&mut self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_pos, self.pos, alpha);
+        let vertices = get_ship_vertices(pos.x, pos.y);
+        // Flickers while nothing can hurt it
+        let flicker = self
+            .effects
+            .iter()
+            .find(|effect| effect.kind == PowerUpKind::Invincible)
+            .is_some_and(|effect| (effect.time_left * 10.0) as i32 % 2 == 0);
+        let color = if flicker {
+            Color::GOLD
+        } else {
+            Color::WHITESMOKE
+        };
+        d.draw_triangle_strip(&vertices, color);
+        // Damaged sections go darker and crack
+        for section in SECTIONS {
+            if !self.is_damaged(section) {
+                continue;
+            }
+            let color = if self.is_broken(section) {
+                Color::DARKGRAY
+            } else {
+                Color::GRAY
+            };
+            let range = section.vertex_range();
+            let center = section_center(&vertices, section);
+            d.draw_triangle_strip(&vertices[range.clone()], color);
+            d.draw_line(vertices[range.start], center, Color::BLACK);
+            d.draw_line(center, vertices[range.end - 1], Color::BLACK);
+        }
+        if self.effects.has(PowerUpKind::Shield) {
+            let center = Vector2 {
+                x: pos.x + 22.5,
+                y: pos.y,
+            };
+            d.draw_circle(center, 30.0, Color::VIOLET.alpha(0.25));
         }
     }
+
+    pub fn fire(&mut self, commands: &mut Commands) {
+        let cooldown_scale = if self.effects.has(PowerUpKind::RapidFire) {
+            RAPID_FIRE_COOLDOWN
+        } else {
+            1.0
+        };
+        let cooldown_scale = if self.is_broken(Section::Cannon) {
+            cooldown_scale * BROKEN_CANNON_COOLDOWN
+        } else {
+            cooldown_scale
+        };
+        let Some(kind) = self.weapons.fire(cooldown_scale) else {
+            return;
+        };
+        // Triple shot fires one more to each side
+        let fan = if self.effects.has(PowerUpKind::TripleShot) {
+            1
+        } else {
+            0
+        };
+        let pos = Vector2 {
+            x: self.pos.x + 15.0,
+            y: self.pos.y + 10.0,
+        };
+        let sonar_range = self.sonar_range;
+        let mut launch = |posd: Vector2| {
+            let bullet = match kind {
+                WeaponKind::Homing => Bullet::homing(pos, posd, sonar_range),
+                _ => Bullet::new(kind, pos, posd),
+            };
+            commands.push(Command::SpawnBullet(bullet));
+        };
+        match kind {
+            WeaponKind::Torpedo => {
+                for i in -fan..=fan {
+                    let angle = i as f32 * SPREAD_ANGLE * 0.5;
+                    launch(Vector2 {
+                        x: angle.cos() * TORPEDO_SPEED,
+                        y: angle.sin() * TORPEDO_SPEED,
+                    });
+                }
+            }
+            WeaponKind::DepthCharge => {
+                for i in -fan..=fan {
+                    launch(Vector2 {
+                        x: self.vel.x * 0.5 + i as f32 * DEPTH_CHARGE_FAN_SPEED,
+                        y: DEPTH_CHARGE_SINK_SPEED,
+                    });
+                }
+            }
+            WeaponKind::Spread => {
+                for i in -1 - fan..=1 + fan {
+                    let angle = i as f32 * SPREAD_ANGLE;
+                    launch(Vector2 {
+                        x: angle.cos() * TORPEDO_SPEED,
+                        y: angle.sin() * TORPEDO_SPEED,
+                    });
+                }
+            }
+            WeaponKind::Homing => {
+                for i in -fan..=fan {
+                    let angle = i as f32 * SPREAD_ANGLE * 0.5;
+                    launch(Vector2 {
+                        x: angle.cos() * HOMING_SPEED,
+                        y: angle.sin() * HOMING_SPEED,
+                    });
+                }
+            }
+        }
+        commands.push(Command::AttachBubbles(Bubbles::new(20), Anchor::Ship));
+    }
+}
+
+fn section_center(vertices: &[Vector2], section: Section) -> Vector2 {
+    let range = section.vertex_range();
+    let count = range.len() as f32;
+    let sum = vertices[range]
+        .iter()
+        .fold(Vector2::zero(), |sum, v| sum + *v);
+    Vector2 {
+        x: sum.x / count,
+        y: sum.y / count,
+    }
+}
+
+impl Collider for Ship {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        get_ship_vertices(self.pos.x, self.pos.y)
+    }
 }
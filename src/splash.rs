@@ -0,0 +1,88 @@
+use std::f32;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::consts::*;
+use crate::entity::{Entity, EntityManager};
+use crate::render::*;
+use crate::timestep::*;
+
+const DURATION: f32 = 0.8;
+const DROPS: usize = 8;
+
+struct Drop {
+    pos: Vector2,
+    prev_pos: Vector2,
+    vel: Vector2,
+    size: f32,
+}
+
+/// Spray thrown up where something breaks through the water surface.
+pub struct Splash {
+    drops: Vec<Drop>,
+    age: f32,
+    finished: bool,
+}
+
+impl Splash {
+    pub fn new(pos: Vector2, rng: &mut StdRng) -> Self {
+        let drops = (0..DROPS)
+            .map(|_| Drop {
+                pos,
+                prev_pos: pos,
+                vel: Vector2 {
+                    x: rng.gen_range(-60.0..60.0),
+                    y: rng.gen_range(-160.0..-60.0),
+                },
+                size: rng.gen_range(1.0..3.0),
+            })
+            .collect();
+        Self {
+            drops,
+            age: 0.0,
+            finished: false,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.finished {
+            return;
+        }
+        self.age += dt;
+        for drop in &mut self.drops {
+            drop.prev_pos = drop.pos;
+            drop.pos.x += drop.vel.x * dt;
+            drop.pos.y += drop.vel.y * dt;
+            drop.vel.y += GRAVITY * dt;
+        }
+        if self.age >= DURATION {
+            self.finished = true;
+        }
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let fade = 1.0 - (self.age / DURATION).min(1.0);
+        for drop in &self.drops {
+            d.draw_circle(
+                lerp(drop.prev_pos, drop.pos, alpha),
+                drop.size,
+                Color::WHITE.alpha(fade),
+            );
+        }
+    }
+}
+
+impl Entity for Splash {
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn set_pos(&mut self, _pos: Vector2) {}
+}
+
+pub type SplashManager = EntityManager<Splash>;
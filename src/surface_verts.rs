@@ -1,8 +1,7 @@
 use std::f32;
 
-use raylib::prelude::*;
-
 use crate::consts::*;
+use crate::render::*;
 use crate::water::*;
 
 pub struct SurfaceVerts {
@@ -21,7 +20,7 @@ impl SurfaceVerts {
     }
 }
 
-fn ease_in_out_quad(x: f32) -> f32 {
+pub fn ease_in_out_quad(x: f32) -> f32 {
     if x < 0.5 {
         2.0 * x * x
     } else {
@@ -84,14 +83,10 @@ pub fn get_surface_verts(surfaces: &Vec<Surface>, x: f32) -> SurfaceVerts {
     }
 }
 
-pub fn draw_surface_verts<'a>(
-    mut d: RaylibDrawHandle<'a>,
-    surface_verts: &SurfaceVerts,
-) -> RaylibDrawHandle<'a> {
+pub fn draw_surface_verts(d: &mut dyn Renderer, surface_verts: &SurfaceVerts) {
     d.draw_triangle_strip(&surface_verts.layer_a, Color::DARKBLUE);
     d.draw_triangle_strip(&surface_verts.layer_b, Color::MEDIUMBLUE);
     d.draw_triangle_strip(&surface_verts.layer_c, Color::LIGHTBLUE);
-    d
 }
 
 pub fn get_surface_verts_index(surface_verts: &SurfaceVerts, x: f32) -> usize {
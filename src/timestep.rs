@@ -0,0 +1,45 @@
+use crate::render::*;
+
+/// The simulation always advances in ticks of this length, whatever the frame rate.
+pub const TICK_DT: f32 = 1.0 / 60.0;
+
+// Tuning values used to be applied once per frame at this rate
+const REFERENCE_FPS: f32 = 60.0;
+
+// A long stall (window drag, breakpoint) must not trigger hundreds of catch-up ticks
+const MAX_FRAME_DT: f32 = 0.25;
+
+/// Turns a factor that was multiplied in once per 60 FPS frame into the one for `dt`.
+pub fn per_frame(factor: f32, dt: f32) -> f32 {
+    factor.powf(dt * REFERENCE_FPS)
+}
+
+pub fn lerp(from: Vector2, to: Vector2, alpha: f32) -> Vector2 {
+    Vector2 {
+        x: from.x + (to.x - from.x) * alpha,
+        y: from.y + (to.y - from.y) * alpha,
+    }
+}
+
+pub struct FixedTimestep {
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new() -> Self {
+        Self { accumulator: 0.0 }
+    }
+
+    /// Returns how many ticks to simulate for a frame that took `frame_dt`.
+    pub fn advance(&mut self, frame_dt: f32) -> usize {
+        self.accumulator += frame_dt.min(MAX_FRAME_DT);
+        let ticks = (self.accumulator / TICK_DT) as usize;
+        self.accumulator -= ticks as f32 * TICK_DT;
+        ticks
+    }
+
+    /// How far rendering is between the last two ticks, 0.0 to 1.0.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / TICK_DT).min(1.0)
+    }
+}
@@ -0,0 +1,214 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::collision::Collider;
+use crate::entity::{Entity, EntityManager};
+use crate::render::*;
+
+pub fn get_coin_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 { x, y: y - 4.0 }, // Top
+        Vector2 { x: x - 4.0, y }, // Left
+        Vector2 { x: x + 4.0, y }, // Right
+        Vector2 { x, y: y + 4.0 }, // Bottom
+    ]
+}
+
+pub fn get_chest_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 {
+            x: x - 9.0,
+            y: y + 6.0,
+        }, // Bottom left
+        Vector2 {
+            x: x - 9.0,
+            y: y - 3.0,
+        }, // Left under the lid
+        Vector2 {
+            x: x + 9.0,
+            y: y + 6.0,
+        }, // Bottom right
+        Vector2 {
+            x: x + 9.0,
+            y: y - 3.0,
+        }, // Right under the lid
+        Vector2 { x, y: y - 8.0 }, // Top of the lid
+    ]
+}
+
+pub fn get_salvage_vertices(x: f32, y: f32) -> Vec<Vector2> {
+    vec![
+        Vector2 {
+            x: x - 10.0,
+            y: y + 5.0,
+        }, // Bent end
+        Vector2 {
+            x: x - 6.0,
+            y: y - 4.0,
+        }, // Torn edge
+        Vector2 {
+            x: x + 4.0,
+            y: y + 5.0,
+        }, // Bottom of the plate
+        Vector2 {
+            x: x + 8.0,
+            y: y - 6.0,
+        }, // Sticking up
+        Vector2 {
+            x: x + 11.0,
+            y: y + 5.0,
+        }, // Far end
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TreasureKind {
+    Coin,
+    Salvage,
+    Chest,
+}
+
+impl TreasureKind {
+    pub fn random(rng: &mut StdRng) -> Self {
+        match rng.gen_range(0..10) {
+            0..=5 => TreasureKind::Coin,
+            6..=8 => TreasureKind::Salvage,
+            _ => TreasureKind::Chest,
+        }
+    }
+
+    pub fn points(self) -> i32 {
+        match self {
+            TreasureKind::Coin => 25,
+            TreasureKind::Salvage => 100,
+            TreasureKind::Chest => 250,
+        }
+    }
+
+    /// What it adds to the gold that is kept between runs.
+    pub fn gold(self) -> i32 {
+        match self {
+            TreasureKind::Coin => 1,
+            TreasureKind::Salvage => 4,
+            TreasureKind::Chest => 10,
+        }
+    }
+
+    // Resting on the floor, how far its middle is above it
+    pub fn height(self) -> f32 {
+        match self {
+            TreasureKind::Coin => 10.0,
+            TreasureKind::Salvage => 5.0,
+            TreasureKind::Chest => 6.0,
+        }
+    }
+
+    fn vertices(self, x: f32, y: f32) -> Vec<Vector2> {
+        match self {
+            TreasureKind::Coin => get_coin_vertices(x, y),
+            TreasureKind::Salvage => get_salvage_vertices(x, y),
+            TreasureKind::Chest => get_chest_vertices(x, y),
+        }
+    }
+}
+
+/// Something lying on the sea floor for the ship to pick up.
+pub struct Treasure {
+    kind: TreasureKind,
+    pos: Vector2,
+    age: f32,
+    arena_x: f32,
+    prev_arena_x: f32,
+    finished: bool,
+}
+
+impl Treasure {
+    /// `x` is in arena coordinates, `y` on screen.
+    pub fn new(kind: TreasureKind, x: f32, y: f32, arena_x: f32) -> Self {
+        Self {
+            kind,
+            pos: Vector2 { x, y },
+            age: 0.0,
+            arena_x,
+            prev_arena_x: arena_x,
+            finished: false,
+        }
+    }
+
+    pub fn screen_pos(&self) -> Vector2 {
+        Vector2 {
+            x: self.arena_x + self.pos.x,
+            y: self.pos.y + self.bob(),
+        }
+    }
+
+    // Coins hover a little above the floor
+    fn bob(&self) -> f32 {
+        match self.kind {
+            TreasureKind::Coin => (self.age * 3.0 + self.pos.x * 0.1).sin() * 2.0,
+            _ => 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, arena_x: f32) {
+        self.prev_arena_x = self.arena_x;
+        self.arena_x = arena_x;
+        self.age += dt;
+        if arena_x + self.pos.x < -20.0 {
+            self.finished = true;
+        }
+    }
+
+    /// Picked up by the ship, returns what it was the first time only.
+    pub fn collect(&mut self) -> Option<TreasureKind> {
+        if self.finished {
+            return None;
+        }
+        self.finished = true;
+        Some(self.kind)
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let arena_x = self.prev_arena_x + (self.arena_x - self.prev_arena_x) * alpha;
+        let pos = Vector2 {
+            x: arena_x + self.pos.x,
+            y: self.pos.y + self.bob(),
+        };
+        let vertices = self.kind.vertices(pos.x, pos.y);
+        match self.kind {
+            TreasureKind::Coin => {
+                d.draw_triangle_strip(&vertices, Color::GOLD);
+            }
+            TreasureKind::Salvage => {
+                d.draw_triangle_strip(&vertices, Color::GRAY);
+            }
+            TreasureKind::Chest => {
+                d.draw_triangle_strip(&vertices, Color::BROWN);
+                // Gold band under the lid and the lock
+                d.draw_line(vertices[1], vertices[3], Color::GOLD);
+                d.draw_circle(pos, 1.5, Color::GOLD);
+            }
+        }
+    }
+}
+
+impl Entity for Treasure {
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn set_pos(&mut self, _pos: Vector2) {}
+}
+
+impl Collider for Treasure {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        let pos = self.screen_pos();
+        self.kind.vertices(pos.x, pos.y)
+    }
+}
+
+pub type TreasureManager = EntityManager<Treasure>;
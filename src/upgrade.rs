@@ -0,0 +1,102 @@
+use crate::bullet::SEEK_RANGE;
+
+pub const MAX_LEVEL: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UpgradeKind {
+    HullArmour,
+    EngineSpeed,
+    TorpedoReload,
+    AmmoCapacity,
+    SonarRange,
+}
+
+pub const UPGRADE_KINDS: [UpgradeKind; 5] = [
+    UpgradeKind::HullArmour,
+    UpgradeKind::EngineSpeed,
+    UpgradeKind::TorpedoReload,
+    UpgradeKind::AmmoCapacity,
+    UpgradeKind::SonarRange,
+];
+
+impl UpgradeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            UpgradeKind::HullArmour => "HULL ARMOUR",
+            UpgradeKind::EngineSpeed => "ENGINE SPEED",
+            UpgradeKind::TorpedoReload => "TORPEDO RELOAD",
+            UpgradeKind::AmmoCapacity => "AMMO CAPACITY",
+            UpgradeKind::SonarRange => "SONAR RANGE",
+        }
+    }
+
+    // Gold for the first level, every further level costs that much more
+    fn base_cost(self) -> u32 {
+        match self {
+            UpgradeKind::HullArmour => 30,
+            UpgradeKind::EngineSpeed => 25,
+            UpgradeKind::TorpedoReload => 25,
+            UpgradeKind::AmmoCapacity => 20,
+            UpgradeKind::SonarRange => 15,
+        }
+    }
+}
+
+/// Levels bought in the shop, they stay with the player from run to run.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Upgrades {
+    levels: [u8; UPGRADE_KINDS.len()],
+}
+
+impl Upgrades {
+    pub fn level(&self, kind: UpgradeKind) -> u8 {
+        self.levels[kind as usize]
+    }
+
+    /// Gold for the next level, `None` once it is maxed out.
+    pub fn next_cost(&self, kind: UpgradeKind) -> Option<u32> {
+        let level = self.level(kind);
+        (level < MAX_LEVEL).then(|| kind.base_cost() * (level as u32 + 1))
+    }
+
+    pub fn raise(&mut self, kind: UpgradeKind) {
+        let level = &mut self.levels[kind as usize];
+        *level = (*level + 1).min(MAX_LEVEL);
+    }
+
+    /// Part of incoming damage that the armour lets through.
+    pub fn armour(&self) -> f32 {
+        1.0 - 0.15 * self.level(UpgradeKind::HullArmour) as f32
+    }
+
+    pub fn thrust(&self) -> f32 {
+        1.0 + 0.1 * self.level(UpgradeKind::EngineSpeed) as f32
+    }
+
+    /// How much faster than normal all weapons reload.
+    pub fn reload_speed(&self) -> f32 {
+        1.0 + 0.25 * self.level(UpgradeKind::TorpedoReload) as f32
+    }
+
+    /// Rounds added to the capacity of every weapon.
+    pub fn extra_ammo(&self) -> i32 {
+        self.level(UpgradeKind::AmmoCapacity) as i32
+    }
+
+    /// How far homing torpedoes find something to lock onto.
+    pub fn sonar_range(&self) -> f32 {
+        SEEK_RANGE * (1.0 + 0.3 * self.level(UpgradeKind::SonarRange) as f32)
+    }
+
+    pub fn to_bytes(self) -> [u8; UPGRADE_KINDS.len()] {
+        self.levels
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut upgrades = Self::default();
+        for (level, byte) in upgrades.levels.iter_mut().zip(bytes) {
+            *level = (*byte).min(MAX_LEVEL);
+        }
+        upgrades
+    }
+}
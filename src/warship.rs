@@ -0,0 +1,217 @@
+use std::f32;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use crate::bullet::*;
+use crate::collision::Collider;
+use crate::commands::*;
+use crate::consts::*;
+use crate::entity::{Entity, EntityManager};
+use crate::explosion::Blast;
+use crate::fish::rotate_point;
+use crate::mine::*;
+use crate::render::*;
+use crate::ship::*;
+use crate::surface_verts::*;
+use crate::timestep::*;
+
+pub fn get_warship_vertices(x: f32, y: f32, angle: f32) -> Vec<Vector2> {
+    [
+        (-32.0, -5.0), // Stern deck
+        (-26.0, 6.0),  // Stern keel
+        (32.0, -5.0),  // Bow deck
+        (24.0, 6.0),   // Bow keel
+    ]
+    .iter()
+    .map(|(vx, vy)| rotate_point(x + vx, y + vy, angle, x, y))
+    .collect()
+}
+
+pub fn get_warship_bridge_vertices(x: f32, y: f32, angle: f32) -> Vec<Vector2> {
+    [
+        (-14.0, -5.0),  // Rear of the bridge
+        (-14.0, -13.0), // Rear roof
+        (6.0, -5.0),    // Front of the bridge
+        (2.0, -13.0),   // Front roof
+    ]
+    .iter()
+    .map(|(vx, vy)| rotate_point(x + vx, y + vy, angle, x, y))
+    .collect()
+}
+
+// Sails against the scroll, so it stays on screen a little longer
+const SAIL_SPEED: f32 = 40.0;
+// Bow and stern sample the surface this far from the middle
+const HALF_LENGTH: f32 = 25.0;
+const HEALTH: i32 = 3;
+const BLAST_RADIUS: f32 = 70.0;
+const SINK_TIME: f32 = 2.0;
+// Drops depth charges instead of mines when the ship is about this close below
+const CHARGE_RANGE: f32 = 60.0;
+
+/// Enemy vessel on the surface that drops the mines and depth charges.
+pub struct Warship {
+    pos: Vector2,
+    angle: f32,
+    prev_angle: f32,
+    age: f32,
+    drop_dt: f32,
+    health: i32,
+    sink_dt: Option<f32>,
+    arena_x: f32,
+    prev_screen_pos: Vector2,
+    finished: bool,
+}
+
+impl Warship {
+    /// `x` is in arena coordinates, like the surface segments.
+    pub fn new(x: f32, arena_x: f32, rng: &mut StdRng) -> Self {
+        let pos = Vector2 { x, y: 0.0 };
+        Self {
+            pos,
+            angle: 0.0,
+            prev_angle: 0.0,
+            age: 0.0,
+            drop_dt: rng.gen_range(1.0..2.0),
+            health: HEALTH,
+            sink_dt: None,
+            arena_x,
+            prev_screen_pos: Vector2 {
+                x: arena_x + x,
+                y: 0.0,
+            },
+            finished: false,
+        }
+    }
+
+    pub fn screen_pos(&self) -> Vector2 {
+        Vector2 {
+            x: self.arena_x + self.pos.x,
+            y: self.pos.y,
+        }
+    }
+
+    pub fn is_sinking(&self) -> bool {
+        self.sink_dt.is_some()
+    }
+
+    pub fn update(
+        &mut self,
+        dt: f32,
+        arena_x: f32,
+        surface_verts: &SurfaceVerts,
+        ship: &Ship,
+        rng: &mut StdRng,
+        commands: &mut Commands,
+    ) {
+        if self.finished {
+            return;
+        }
+        self.prev_screen_pos = self.screen_pos();
+        self.prev_angle = self.angle;
+        self.arena_x = arena_x;
+        self.age += dt;
+
+        if let Some(sink_dt) = &mut self.sink_dt {
+            *sink_dt += dt;
+            self.pos.y += dt * 30.0;
+            self.angle += dt * 0.4;
+            if *sink_dt >= SINK_TIME {
+                self.finished = true;
+            }
+            return;
+        }
+
+        self.pos.x += dt * SAIL_SPEED;
+        let x = arena_x + self.pos.x;
+        if x < -HALF_LENGTH * 2.0 {
+            self.finished = true;
+            return;
+        }
+
+        // Pitch with the waves between bow and stern, plus a little bob
+        let stern = surface_verts.layer_a[get_surface_verts_index(surface_verts, x - HALF_LENGTH)];
+        let bow = surface_verts.layer_a[get_surface_verts_index(surface_verts, x + HALF_LENGTH)];
+        let target_angle = (bow.y - stern.y).atan2(HALF_LENGTH * 2.0);
+        let smoothing = per_frame(0.9, dt);
+        self.angle = self.angle * smoothing + target_angle * (1.0 - smoothing);
+        self.pos.y = (bow.y + stern.y) * 0.5 + (self.age * 3.0).sin() * 1.5;
+
+        // Only drops what the player can see
+        if !(0.0..WINDOW_WIDTH as f32).contains(&x) {
+            return;
+        }
+        self.drop_dt -= dt;
+        if self.drop_dt > 0.0 {
+            return;
+        }
+        self.drop_dt = rng.gen_range(1.5..3.5);
+        let drop_pos = rotate_point(x - 20.0, self.pos.y + 6.0, self.angle, x, self.pos.y);
+        if (ship.center().x - x).abs() < CHARGE_RANGE {
+            commands.push(Command::SpawnBullet(Bullet::dropped_charge(
+                drop_pos, ship.pos.y,
+            )));
+        } else {
+            commands.push(Command::SpawnMine(Mine::new(
+                MineKind::random(rng),
+                drop_pos,
+                arena_x,
+                ship,
+            )));
+        }
+    }
+
+    /// Returns true when this hit sank it.
+    pub fn hit(&mut self, commands: &mut Commands) -> bool {
+        if self.finished || self.is_sinking() {
+            return false;
+        }
+        self.health -= 1;
+        if self.health > 0 {
+            return false;
+        }
+        self.sink_dt = Some(0.0);
+        commands.push(Command::Explode(Blast::new(
+            self.screen_pos(),
+            BLAST_RADIUS,
+        )));
+        true
+    }
+
+    pub fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        let pos = lerp(self.prev_screen_pos, self.screen_pos(), alpha);
+        let angle = self.prev_angle + (self.angle - self.prev_angle) * alpha;
+        let color = if self.is_sinking() {
+            Color::DARKGRAY
+        } else {
+            Color::DIMGRAY
+        };
+        d.draw_triangle_strip(
+            &get_warship_bridge_vertices(pos.x, pos.y, angle),
+            Color::GRAY,
+        );
+        d.draw_triangle_strip(&get_warship_vertices(pos.x, pos.y, angle), color);
+    }
+}
+
+impl Entity for Warship {
+    fn draw(&self, d: &mut dyn Renderer, alpha: f32) {
+        self.draw(d, alpha)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn set_pos(&mut self, _pos: Vector2) {}
+}
+
+impl Collider for Warship {
+    fn hit_shape(&self) -> Vec<Vector2> {
+        let pos = self.screen_pos();
+        get_warship_vertices(pos.x, pos.y, self.angle)
+    }
+}
+
+pub type WarshipManager = EntityManager<Warship>;
@@ -1,9 +1,10 @@
 use std::f32;
 
+use rand::rngs::StdRng;
 use rand::Rng;
-use raylib::prelude::*;
 
 use crate::consts::*;
+use crate::render::*;
 use crate::surface_verts::*;
 
 pub struct Surface {
@@ -27,6 +28,8 @@ impl Surface {
 pub struct Water {
     surfaces: Vec<Surface>,
     pub surface_verts: SurfaceVerts,
+    arena_x: f32,
+    prev_arena_x: f32,
 }
 
 impl Water {
@@ -34,11 +37,14 @@ impl Water {
         Self {
             surfaces: vec![Surface::new()],
             surface_verts: SurfaceVerts::new(),
+            arena_x: 0.0,
+            prev_arena_x: 0.0,
         }
     }
 
-    pub fn update(&mut self, arena_x: f32) -> Option<(i32, Vector2)> {
-        let mut rng = rand::thread_rng();
+    pub fn update(&mut self, arena_x: f32, rng: &mut StdRng) -> Option<(i32, Vector2)> {
+        self.prev_arena_x = self.arena_x;
+        self.arena_x = arena_x;
         let mut result: Option<(i32, Vector2)> = None;
         loop {
             let surface = &self.surfaces[self.surfaces.len() - 1];
@@ -72,7 +78,16 @@ impl Water {
         }
     }
 
-    pub fn draw<'a>(&mut self, d: RaylibDrawHandle<'a>) -> RaylibDrawHandle<'a> {
-        draw_surface_verts(d, &self.surface_verts)
+    /// Height of the surface segment starting at world `x`, up from the bottom.
+    pub fn surface_height(&self, x: f32) -> Option<f32> {
+        self.surfaces
+            .iter()
+            .find(|surface| surface.pos.x == x)
+            .map(|surface| surface.pos.y)
+    }
+
+    pub fn draw(&mut self, d: &mut dyn Renderer, alpha: f32) {
+        let arena_x = self.prev_arena_x + (self.arena_x - self.prev_arena_x) * alpha;
+        draw_surface_verts(d, &get_surface_verts(&self.surfaces, arena_x))
     }
 }
@@ -0,0 +1,147 @@
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WeaponKind {
+    Torpedo,
+    DepthCharge,
+    Spread,
+    Homing,
+}
+
+impl WeaponKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            WeaponKind::Torpedo => "TORPEDO",
+            WeaponKind::DepthCharge => "DEPTH CHARGE",
+            WeaponKind::Spread => "SPREAD",
+            WeaponKind::Homing => "HOMING",
+        }
+    }
+
+    pub fn capacity(self) -> i32 {
+        match self {
+            WeaponKind::Torpedo => 6,
+            WeaponKind::DepthCharge => 4,
+            WeaponKind::Spread => 3,
+            WeaponKind::Homing => 2,
+        }
+    }
+
+    pub fn blast_radius(self) -> f32 {
+        match self {
+            WeaponKind::Torpedo => 25.0,
+            WeaponKind::DepthCharge => 70.0,
+            WeaponKind::Spread => 20.0,
+            WeaponKind::Homing => 30.0,
+        }
+    }
+
+    // Seconds between two shots
+    fn cooldown(self) -> f32 {
+        match self {
+            WeaponKind::Torpedo => 0.4,
+            WeaponKind::DepthCharge => 0.8,
+            WeaponKind::Spread => 1.0,
+            WeaponKind::Homing => 1.0,
+        }
+    }
+
+    // Seconds until one more round is back
+    fn reload_time(self) -> f32 {
+        match self {
+            WeaponKind::Torpedo => 1.5,
+            WeaponKind::DepthCharge => 3.0,
+            WeaponKind::Spread => 4.0,
+            WeaponKind::Homing => 6.0,
+        }
+    }
+}
+
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub ammo: i32,
+    // The kind's capacity plus upgrades
+    pub capacity: i32,
+    reload_dt: f32,
+}
+
+impl Weapon {
+    pub fn new(kind: WeaponKind, extra_ammo: i32) -> Self {
+        let capacity = kind.capacity() + extra_ammo;
+        Self {
+            kind,
+            ammo: capacity,
+            capacity,
+            reload_dt: 0.0,
+        }
+    }
+
+    /// How far the next round is reloaded, 1.0 when the weapon is full.
+    pub fn reload_progress(&self) -> f32 {
+        if self.ammo >= self.capacity {
+            return 1.0;
+        }
+        self.reload_dt / self.kind.reload_time()
+    }
+}
+
+/// The ship's weapons. All of them reload all the time, only the selected one fires.
+pub struct Weapons {
+    slots: Vec<Weapon>,
+    selected: usize,
+    cooldown_dt: f32,
+    reload_speed: f32,
+}
+
+impl Weapons {
+    /// `reload_speed` above 1.0 brings rounds back sooner.
+    pub fn new(reload_speed: f32, extra_ammo: i32) -> Self {
+        Self {
+            slots: [
+                WeaponKind::Torpedo,
+                WeaponKind::DepthCharge,
+                WeaponKind::Spread,
+                WeaponKind::Homing,
+            ]
+            .into_iter()
+            .map(|kind| Weapon::new(kind, extra_ammo))
+            .collect(),
+            selected: 0,
+            cooldown_dt: 0.0,
+            reload_speed,
+        }
+    }
+
+    pub fn selected(&self) -> &Weapon {
+        &self.slots[self.selected]
+    }
+
+    pub fn switch(&mut self) {
+        self.selected = (self.selected + 1) % self.slots.len();
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.cooldown_dt = (self.cooldown_dt - dt).max(0.0);
+        for weapon in &mut self.slots {
+            if weapon.ammo >= weapon.capacity {
+                weapon.reload_dt = 0.0;
+                continue;
+            }
+            weapon.reload_dt += dt * self.reload_speed;
+            if weapon.reload_dt >= weapon.kind.reload_time() {
+                weapon.reload_dt -= weapon.kind.reload_time();
+                weapon.ammo += 1;
+            }
+        }
+    }
+
+    /// Takes a round from the selected weapon if it is ready to fire.
+    /// `cooldown_scale` below 1.0 lets the next shot come sooner.
+    pub fn fire(&mut self, cooldown_scale: f32) -> Option<WeaponKind> {
+        let weapon = &mut self.slots[self.selected];
+        if self.cooldown_dt > 0.0 || weapon.ammo <= 0 {
+            return None;
+        }
+        weapon.ammo -= 1;
+        self.cooldown_dt = weapon.kind.cooldown() * cooldown_scale;
+        Some(weapon.kind)
+    }
+}
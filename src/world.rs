@@ -0,0 +1,591 @@
+use rand::Rng;
+
+use crate::boss::*;
+use crate::bubbles::*;
+use crate::bullet::*;
+use crate::collision::*;
+use crate::commands::*;
+use crate::consts::*;
+use crate::enemy_sub::*;
+use crate::entity::{Entity, EntityId};
+use crate::explosion::*;
+use crate::fish_swarm::*;
+use crate::game::Input;
+use crate::hud::*;
+use crate::mine::*;
+use crate::power_up::*;
+use crate::render::*;
+use crate::rng::*;
+use crate::score::*;
+use crate::seabed::*;
+use crate::ship::*;
+use crate::splash::*;
+use crate::timestep::*;
+use crate::treasure::*;
+use crate::upgrade::*;
+use crate::warship::*;
+use crate::water::*;
+
+// Damage right at the center of a blast, falls off towards the edge
+const BLAST_DAMAGE: f32 = 60.0;
+const BURST_BUBBLES: usize = 15;
+// Per second while the ship scrapes along rock
+const SCRAPE_DAMAGE: f32 = 20.0;
+// Per second while the ship has run out of air
+const SUFFOCATION_DAMAGE: f32 = 10.0;
+// Per second while the ship touches a boss
+const BOSS_CONTACT_DAMAGE: f32 = 40.0;
+const SCROLL_SPEED: f32 = 100.0;
+// How quickly the scroll stops for a boss and picks up again
+const SCROLL_EASING: f32 = 0.98;
+// Arena distances where bosses show up, after the last one every BOSS_INTERVAL
+const BOSS_MILESTONES: [f32; 3] = [4000.0, 10000.0, 18000.0];
+const BOSS_INTERVAL: f32 = 10000.0;
+// Chance for treasure with each new surface segment
+const TREASURE_CHANCE: f64 = 0.4;
+const COIN_SPACING: f32 = 12.0;
+// Chance for a power-up to come up from the floor with each new surface segment
+const POWER_UP_CHANCE: f64 = 0.15;
+const MAX_WARSHIPS: usize = 2;
+const MAX_ENEMY_SUBS: usize = 2;
+// Chance for a submarine with each new surface segment
+const ENEMY_SUB_CHANCE: f64 = 0.2;
+// Room kept between a new submarine and the sea floor below it
+const ENEMY_SUB_FLOOR_CLEARANCE: f32 = 30.0;
+
+/// Everything that belongs to a single run, dropped and rebuilt on restart.
+pub struct World {
+    arena_x: f32,
+    scroll_speed: f32,
+    water: Water,
+    seabed: Seabed,
+    bubbles_manager: BubblesManager,
+    bullet_manager: BulletManager,
+    enemy_sub_manager: EnemySubManager,
+    explosion_manager: ExplosionManager,
+    fish_swarm_manager: FishSwarmManager,
+    mine_manager: MineManager,
+    power_up_manager: PowerUpManager,
+    splash_manager: SplashManager,
+    treasure_manager: TreasureManager,
+    warship_manager: WarshipManager,
+    boss: Option<Boss>,
+    bosses_met: usize,
+    ship: Ship,
+    score: Score,
+    // Part of the score's gold already handed to the bank
+    gold_banked: i32,
+    rng: GameRng,
+    commands: Commands,
+}
+
+impl World {
+    pub fn new(seed: u64, upgrades: &Upgrades) -> Self {
+        Self {
+            arena_x: 0.0,
+            scroll_speed: SCROLL_SPEED,
+            water: Water::new(),
+            seabed: Seabed::new(),
+            bubbles_manager: BubblesManager::new(),
+            bullet_manager: BulletManager::new(),
+            enemy_sub_manager: EnemySubManager::new(),
+            explosion_manager: ExplosionManager::new(),
+            fish_swarm_manager: FishSwarmManager::new(),
+            mine_manager: MineManager::new(),
+            power_up_manager: PowerUpManager::new(),
+            splash_manager: SplashManager::new(),
+            treasure_manager: TreasureManager::new(),
+            warship_manager: WarshipManager::new(),
+            boss: None,
+            bosses_met: 0,
+            ship: Ship::new(upgrades),
+            score: Score::new(),
+            gold_banked: 0,
+            rng: GameRng::new(seed),
+            commands: Commands::new(),
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.score.is_game_over()
+    }
+
+    pub fn update(&mut self, dt: f32, input: &Input) {
+        let lives = self.score.lives;
+        // The scroll holds still while a boss is around
+        let scroll_target = if self.boss.is_some() {
+            0.0
+        } else {
+            SCROLL_SPEED
+        };
+        let easing = per_frame(SCROLL_EASING, dt);
+        self.scroll_speed = scroll_target + (self.scroll_speed - scroll_target) * easing;
+        self.arena_x -= dt * self.scroll_speed;
+        let arena_x = self.arena_x;
+        self.score.update(arena_x);
+
+        if self.boss.is_none() && -arena_x >= boss_milestone(self.bosses_met) {
+            let kind = match self.bosses_met % 2 {
+                0 => BossKind::Squid,
+                _ => BossKind::MotherSub,
+            };
+            self.boss = Some(Boss::new(kind, self.ship.center().y));
+            self.bosses_met += 1;
+        }
+        if let Some(boss) = &mut self.boss {
+            boss.update(
+                dt,
+                arena_x,
+                &self.ship,
+                &self.water.surface_verts,
+                &self.seabed,
+                &mut self.commands,
+            );
+            if boss.is_finished() {
+                self.boss = None;
+            }
+        }
+
+        let ship = &self.ship;
+        let surface_verts = &self.water.surface_verts;
+        let seabed = &self.seabed;
+        let commands = &mut self.commands;
+        let mines_rng = &mut self.rng.mines;
+        self.warship_manager.update(|warship, _, _| {
+            warship.update(dt, arena_x, surface_verts, ship, mines_rng, commands);
+        });
+        for (_, bullet) in self.bullet_manager.iter() {
+            if bullet.is_finished() || bullet.is_hostile() {
+                continue;
+            }
+            for (_, enemy_sub) in self.enemy_sub_manager.iter_mut() {
+                enemy_sub.dodge(bullet.pos(), bullet.vel());
+            }
+        }
+        self.enemy_sub_manager.update(|enemy_sub, _, _| {
+            enemy_sub.update(dt, arena_x, ship, surface_verts, seabed, commands);
+        });
+        self.mine_manager.update(|entity, _, id| {
+            entity.update(dt, arena_x, id, ship, seabed, commands);
+            // Mines that run into rock go off
+            let pos = entity.screen_pos();
+            if seabed.is_rock(pos.x, pos.y) {
+                entity.detonate(commands);
+            }
+        });
+        let new_surface = self.water.update(arena_x, &mut self.rng.water);
+        self.seabed
+            .update(arena_x, &self.water, &mut self.rng.terrain);
+        if let Some((step, surface_pos)) = new_surface {
+            if step == 0 && self.warship_manager.iter().count() < MAX_WARSHIPS {
+                self.warship_manager.insert(Warship::new(
+                    surface_pos.x,
+                    arena_x,
+                    &mut self.rng.mines,
+                ));
+            }
+            let enemies_rng = &mut self.rng.enemies;
+            if self.enemy_sub_manager.iter().count() < MAX_ENEMY_SUBS
+                && enemies_rng.gen_bool(ENEMY_SUB_CHANCE)
+            {
+                let profile = Profile::random(enemies_rng);
+                // The surface height counts up from the bottom of the window
+                let surface_y = WINDOW_HEIGHT as f32 - surface_pos.y;
+                let floor_y = self.seabed.floor_y(arena_x + surface_pos.x);
+                let y = (surface_y + enemies_rng.gen_range(60.0..150.0))
+                    .min(floor_y - ENEMY_SUB_FLOOR_CLEARANCE);
+                self.enemy_sub_manager
+                    .insert(EnemySub::new(profile, surface_pos.x, y, arena_x));
+            }
+            self.spawn_treasure(surface_pos.x);
+            self.spawn_power_up(surface_pos.x);
+        }
+
+        let surface_verts = &self.water.surface_verts;
+        let seabed = &self.seabed;
+        let rng = &mut self.rng;
+        let mut has_lead_fish_in_last_sector = false;
+        self.fish_swarm_manager.update(|fish_swarm, _, _| {
+            if fish_swarm.update(dt, surface_verts, seabed, &mut rng.fish) {
+                // no fish in swarm
+            }
+            if fish_swarm.in_last_sector() {
+                has_lead_fish_in_last_sector = true;
+            }
+        });
+
+        if !has_lead_fish_in_last_sector {
+            let fish_rng = &mut self.rng.fish;
+            let count = fish_rng.gen_range(10..30);
+            let relaxed = fish_rng.gen_range(0..9);
+            self.fish_swarm_manager
+                .insert(FishSwarm::new(count, relaxed, fish_rng));
+        }
+
+        let bubbles_rng = &mut self.rng.bubbles;
+        self.bubbles_manager
+            .update(|bubbles, _, _| bubbles.update(dt, surface_verts, bubbles_rng));
+        let targets: Vec<(Target, Vector2)> = self
+            .mine_manager
+            .iter()
+            .filter(|(_, mine)| !mine.is_finished())
+            .map(|(id, mine)| (Target::Mine(id), mine.screen_pos()))
+            .chain(
+                self.warship_manager
+                    .iter()
+                    .filter(|(_, warship)| !warship.is_finished() && !warship.is_sinking())
+                    .map(|(id, warship)| (Target::Warship(id), warship.screen_pos())),
+            )
+            .chain(
+                self.enemy_sub_manager
+                    .iter()
+                    .filter(|(_, enemy_sub)| !enemy_sub.is_finished())
+                    .map(|(id, enemy_sub)| (Target::EnemySub(id), enemy_sub.center())),
+            )
+            .chain(
+                self.boss
+                    .iter()
+                    .flat_map(|boss| boss.weak_spots())
+                    .map(|(index, pos)| (Target::WeakPoint(index), pos)),
+            )
+            .collect();
+        let commands = &mut self.commands;
+        self.bullet_manager.update(|bullet, _, id| {
+            bullet.update(dt, id, &targets, surface_verts, seabed, commands)
+        });
+        self.explosion_manager
+            .update(|explosion, _, _| explosion.update(dt));
+        self.splash_manager.update(|splash, _, _| splash.update(dt));
+        self.treasure_manager
+            .update(|treasure, _, _| treasure.update(dt, arena_x));
+        let current = self.scroll_speed;
+        self.power_up_manager
+            .update(|power_up, _, _| power_up.update(dt, current, surface_verts));
+        self.ship
+            .update(dt, input, surface_verts, seabed, &mut self.commands);
+        if let Some(pos) = self.ship.scrape_pos() {
+            self.score
+                .damage(self.ship.take_damage(SCRAPE_DAMAGE * dt, pos));
+        }
+        // No power-up helps against that
+        if self.ship.is_out_of_air() {
+            self.score.damage(SUFFOCATION_DAMAGE * dt);
+        }
+
+        self.handle_contacts(dt);
+
+        if input.fire {
+            self.ship.fire(&mut self.commands);
+        }
+
+        self.apply_commands();
+        self.apply_blasts();
+        self.follow_anchors();
+
+        if self.score.lives < lives {
+            self.ship.refit();
+        }
+    }
+
+    fn apply_commands(&mut self) {
+        for command in self.commands.take() {
+            match command {
+                Command::SpawnBullet(bullet) => {
+                    self.bullet_manager.insert(bullet);
+                }
+                Command::SpawnMine(mine) => {
+                    self.mine_manager.insert(mine);
+                }
+                Command::AttachBubbles(mut bubbles, anchor) => {
+                    bubbles.attach(anchor);
+                    self.bubbles_manager.insert(bubbles);
+                }
+                Command::DespawnBullet(bullet_id) => {
+                    self.bullet_manager.remove(bullet_id);
+                }
+                Command::Explode(blast) => {
+                    if blast.chain > 0 {
+                        self.score.chain_kill(blast.chain);
+                    }
+                    self.explosion_manager
+                        .insert(Explosion::new(blast, &mut self.rng.effects));
+                    self.bubbles_manager
+                        .insert(Bubbles::burst(BURST_BUBBLES, blast.pos));
+                }
+                Command::Splash(pos) => {
+                    self.splash_manager
+                        .insert(Splash::new(pos, &mut self.rng.effects));
+                }
+            }
+        }
+    }
+
+    // Fresh explosions hurt the ship, scare fish away and light the fuses of nearby mines
+    fn apply_blasts(&mut self) {
+        for (_, explosion) in self.explosion_manager.iter_mut() {
+            let Some(blast) = explosion.take_blast() else {
+                continue;
+            };
+            let dist = self.ship.center().distance_to(blast.pos);
+            if dist < blast.radius {
+                let damage = BLAST_DAMAGE * (1.0 - dist / blast.radius);
+                self.score.damage(self.ship.take_damage(damage, blast.pos));
+            }
+            for (_, fish_swarm) in self.fish_swarm_manager.iter_mut() {
+                fish_swarm.scatter_from(blast.pos, blast.radius);
+            }
+            for (_, mine) in self.mine_manager.iter_mut() {
+                if mine.screen_pos().distance_to(blast.pos) < blast.radius {
+                    mine.ignite(blast.chain + 1);
+                }
+            }
+            if let Some(boss) = &mut self.boss {
+                if boss.blast(blast.pos, blast.radius) {
+                    self.score.bosses_defeated += 1;
+                }
+            }
+            for (_, enemy_sub) in self.enemy_sub_manager.iter_mut() {
+                if enemy_sub.center().distance_to(blast.pos) < blast.radius
+                    && enemy_sub.hit(&mut self.commands)
+                {
+                    self.score.subs_sunk += 1;
+                }
+            }
+        }
+    }
+
+    // Attached bubbles keep coming out of whatever they are attached to
+    fn follow_anchors(&mut self) {
+        for (_, bubbles) in self.bubbles_manager.iter_mut() {
+            let pos = match bubbles.anchor() {
+                Some(Anchor::Ship) => Some(self.ship.exhaust_pos()),
+                Some(Anchor::ShipSection(section)) => Some(self.ship.section_pos(section)),
+                Some(Anchor::Mine(mine_id)) => {
+                    self.mine_manager.get(mine_id).map(|mine| mine.screen_pos())
+                }
+                Some(Anchor::Bullet(bullet_id)) => self
+                    .bullet_manager
+                    .get(bullet_id)
+                    .map(|bullet| bullet.pos()),
+                None => None,
+            };
+            if let Some(pos) = pos {
+                bubbles.set_pos(pos);
+            }
+        }
+    }
+
+    fn handle_contacts(&mut self, dt: f32) {
+        let contacts = detect_contacts(
+            &self.ship,
+            &self.bullet_manager,
+            &self.mine_manager,
+            &self.fish_swarm_manager,
+            &self.warship_manager,
+            &self.enemy_sub_manager,
+            self.boss.as_ref(),
+        );
+        let mut spent_bullets: Vec<EntityId> = vec![];
+        for contact in contacts {
+            match contact {
+                Contact::ShipMine(mine_id) => {
+                    if let Some(mine) = self.mine_manager.get_mut(mine_id) {
+                        mine.detonate(&mut self.commands);
+                    }
+                }
+                Contact::BulletMine(bullet_id, mine_id) => {
+                    // A bullet touching two things at once only takes out the first
+                    if spent_bullets.contains(&bullet_id) {
+                        continue;
+                    }
+                    spent_bullets.push(bullet_id);
+                    self.commands.push(Command::DespawnBullet(bullet_id));
+                    if let Some(mine) = self.mine_manager.get_mut(mine_id) {
+                        // A lit mine is scored as part of its chain, a finished one already was
+                        if !mine.is_finished() && !mine.is_lit() {
+                            self.score.mines_destroyed += 1;
+                        }
+                        mine.detonate(&mut self.commands);
+                    }
+                }
+                Contact::BulletFish(bullet_id, swarm_id, fish_id) => {
+                    if spent_bullets.contains(&bullet_id) {
+                        continue;
+                    }
+                    spent_bullets.push(bullet_id);
+                    self.commands.push(Command::DespawnBullet(bullet_id));
+                    self.kill_fish(swarm_id, fish_id);
+                }
+                Contact::ShipFish(swarm_id, fish_id) => {
+                    self.kill_fish(swarm_id, fish_id);
+                }
+                Contact::BulletWarship(bullet_id, warship_id) => {
+                    if spent_bullets.contains(&bullet_id) {
+                        continue;
+                    }
+                    spent_bullets.push(bullet_id);
+                    self.commands.push(Command::DespawnBullet(bullet_id));
+                    if let Some(warship) = self.warship_manager.get_mut(warship_id) {
+                        if warship.hit(&mut self.commands) {
+                            self.score.warships_sunk += 1;
+                        }
+                    }
+                }
+                Contact::BulletEnemySub(bullet_id, sub_id) => {
+                    if spent_bullets.contains(&bullet_id) {
+                        continue;
+                    }
+                    spent_bullets.push(bullet_id);
+                    self.commands.push(Command::DespawnBullet(bullet_id));
+                    if let Some(enemy_sub) = self.enemy_sub_manager.get_mut(sub_id) {
+                        if enemy_sub.hit(&mut self.commands) {
+                            self.score.subs_sunk += 1;
+                        }
+                    }
+                }
+                Contact::ShipEnemySub(sub_id) => {
+                    if let Some(enemy_sub) = self.enemy_sub_manager.get_mut(sub_id) {
+                        if enemy_sub.wreck(&mut self.commands) {
+                            self.score.subs_sunk += 1;
+                        }
+                    }
+                }
+                Contact::BulletBoss(bullet_id) => {
+                    // Goes off against the hull, only blasts near a weak point hurt
+                    if spent_bullets.contains(&bullet_id) {
+                        continue;
+                    }
+                    spent_bullets.push(bullet_id);
+                    if let Some(bullet) = self.bullet_manager.get_mut(bullet_id) {
+                        bullet.detonate(&mut self.commands);
+                    }
+                }
+                Contact::ShipBoss => {
+                    if let Some(boss) = &self.boss {
+                        let damage = self.ship.take_damage(BOSS_CONTACT_DAMAGE * dt, boss.pos());
+                        self.score.damage(damage);
+                    }
+                }
+                Contact::ShipBullet(bullet_id) => {
+                    if let Some(bullet) = self.bullet_manager.get_mut(bullet_id) {
+                        bullet.detonate(&mut self.commands);
+                    }
+                }
+            }
+        }
+
+        for treasure_id in detect_pickups(&self.ship, &self.treasure_manager) {
+            if let Some(kind) = self
+                .treasure_manager
+                .get_mut(treasure_id)
+                .and_then(|treasure| treasure.collect())
+            {
+                self.score.collect(kind);
+            }
+        }
+        for power_up_id in detect_pickups(&self.ship, &self.power_up_manager) {
+            if let Some(kind) = self
+                .power_up_manager
+                .get_mut(power_up_id)
+                .and_then(|power_up| power_up.collect())
+            {
+                self.ship.effects.add(kind);
+            }
+        }
+    }
+
+    // Lies on the floor a little behind the surface segment at arena `x`, coins come in a row
+    fn spawn_treasure(&mut self, x: f32) {
+        let loot_rng = &mut self.rng.loot;
+        if !loot_rng.gen_bool(TREASURE_CHANCE) {
+            return;
+        }
+        let kind = TreasureKind::random(loot_rng);
+        let count = match kind {
+            TreasureKind::Coin => loot_rng.gen_range(3..=5),
+            _ => 1,
+        };
+        let x = x - loot_rng.gen_range(50.0..90.0);
+        for i in 0..count {
+            let x = x + i as f32 * COIN_SPACING;
+            let screen_x = self.arena_x + x;
+            let y = self.seabed.floor_y(screen_x) - kind.height();
+            if !self.seabed.is_rock(screen_x, y) {
+                self.treasure_manager
+                    .insert(Treasure::new(kind, x, y, self.arena_x));
+            }
+        }
+    }
+
+    // Starts on the floor a little behind the surface segment at arena `x` and rises from there
+    fn spawn_power_up(&mut self, x: f32) {
+        let loot_rng = &mut self.rng.loot;
+        if !loot_rng.gen_bool(POWER_UP_CHANCE) {
+            return;
+        }
+        let kind = PowerUpKind::random(loot_rng);
+        let screen_x = self.arena_x + x - loot_rng.gen_range(20.0..60.0);
+        let y = self.seabed.floor_y(screen_x) - 10.0;
+        if !self.seabed.is_rock(screen_x, y) {
+            self.power_up_manager
+                .insert(PowerUp::new(kind, Vector2 { x: screen_x, y }));
+        }
+    }
+
+    /// Gold collected since the last call, for the bank.
+    pub fn take_gold(&mut self) -> i32 {
+        let gold = self.score.gold - self.gold_banked;
+        self.gold_banked = self.score.gold;
+        gold
+    }
+
+    fn kill_fish(&mut self, swarm_id: EntityId, fish_id: EntityId) {
+        if let Some(fish_swarm) = self.fish_swarm_manager.get_mut(swarm_id) {
+            if fish_swarm.remove_fish(fish_id).is_some() {
+                self.score.fish_hit += 1;
+            }
+        }
+    }
+
+    /// `alpha` is the position between the previous and the current tick.
+    pub fn draw(&mut self, d: &mut dyn Renderer, alpha: f32) {
+        d.clear_background(Color::LIGHTSKYBLUE);
+        self.water.draw(d, alpha);
+        self.seabed.draw(d, alpha);
+        self.treasure_manager.draw(d, alpha);
+        self.warship_manager.draw(d, alpha);
+        self.fish_swarm_manager.draw(d, alpha);
+        self.bullet_manager.draw(d, alpha);
+        self.bubbles_manager.draw(d, alpha);
+        self.mine_manager.draw(d, alpha);
+        self.power_up_manager.draw(d, alpha);
+        self.enemy_sub_manager.draw(d, alpha);
+        if let Some(boss) = &self.boss {
+            boss.draw(d, alpha);
+        }
+        self.explosion_manager.draw(d, alpha);
+        self.splash_manager.draw(d, alpha);
+        self.ship.draw(d, alpha);
+        draw_hud(
+            d,
+            &self.score,
+            &self.ship.weapons,
+            &self.ship.effects,
+            self.ship.air / MAX_AIR,
+            self.rng.seed(),
+        );
+        if let Some(boss) = &self.boss {
+            draw_boss_bar(d, boss.name(), boss.health_fraction());
+        }
+    }
+}
+
+fn boss_milestone(index: usize) -> f32 {
+    match BOSS_MILESTONES.get(index) {
+        Some(milestone) => *milestone,
+        None => {
+            let last = BOSS_MILESTONES[BOSS_MILESTONES.len() - 1];
+            last + (index + 1 - BOSS_MILESTONES.len()) as f32 * BOSS_INTERVAL
+        }
+    }
+}